@@ -0,0 +1,65 @@
+use {
+    advent_of_code_2020::days::d11::{
+        num_seats_with_behavior, Part1OccupantBehavior, Part2OccupantBehavior, ThresholdBehavior,
+        WaitingAreaMap, WaitingAreaSeatingSimulation,
+    },
+    criterion::{criterion_group, criterion_main, BatchSize, Criterion},
+};
+
+const INPUT: &str = include_str!("../src/days/d11.txt");
+
+fn simulation(c: &mut Criterion) {
+    c.bench_function("d11 part 1", |b| {
+        b.iter(|| num_seats_with_behavior(Part1OccupantBehavior).unwrap())
+    });
+    c.bench_function("d11 part 2", |b| {
+        b.iter(|| num_seats_with_behavior(Part2OccupantBehavior).unwrap())
+    });
+
+    let map = INPUT.parse::<WaitingAreaMap>().unwrap();
+    c.bench_function("d11 part 1 first step", |b| {
+        b.iter_batched(
+            || WaitingAreaSeatingSimulation::new(map.clone()),
+            |mut simulation| simulation.next_step(Part1OccupantBehavior).is_some(),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("d11 part 2 first step", |b| {
+        b.iter_batched(
+            || WaitingAreaSeatingSimulation::new(map.clone()),
+            |mut simulation| simulation.next_step(Part2OccupantBehavior).is_some(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn precomputed_simulation(c: &mut Criterion) {
+    let map = INPUT.parse::<WaitingAreaMap>().unwrap();
+
+    c.bench_function("d11 part 1 precomputed", |b| {
+        b.iter(|| num_seats_with_behavior(ThresholdBehavior::PART_1.precompute(&map)).unwrap())
+    });
+    c.bench_function("d11 part 2 precomputed", |b| {
+        b.iter(|| num_seats_with_behavior(ThresholdBehavior::PART_2.precompute(&map)).unwrap())
+    });
+
+    let mut part_1_behavior = ThresholdBehavior::PART_1.precompute(&map);
+    c.bench_function("d11 part 1 first step precomputed", |b| {
+        b.iter_batched(
+            || WaitingAreaSeatingSimulation::new(map.clone()),
+            |mut simulation| simulation.next_step(&mut part_1_behavior).is_some(),
+            BatchSize::SmallInput,
+        )
+    });
+    let mut part_2_behavior = ThresholdBehavior::PART_2.precompute(&map);
+    c.bench_function("d11 part 2 first step precomputed", |b| {
+        b.iter_batched(
+            || WaitingAreaSeatingSimulation::new(map.clone()),
+            |mut simulation| simulation.next_step(&mut part_2_behavior).is_some(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, simulation, precomputed_simulation);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+use {
+    advent_of_code_2020::days::solve,
+    anyhow::Context,
+    std::io::{self, Read},
+    structopt::StructOpt,
+};
+
+/// Solves an Advent of Code 2020 puzzle, reading puzzle input from stdin.
+#[derive(Debug, StructOpt)]
+struct Args {
+    /// The day of the puzzle to solve.
+    #[structopt(long)]
+    day: u8,
+    /// Which part of the day's puzzle to solve.
+    #[structopt(long, possible_values = &["1", "2"])]
+    part: u8,
+}
+
+fn main() -> anyhow::Result<()> {
+    let Args { day, part } = Args::from_args();
+
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .context("failed to read puzzle input from stdin")?;
+
+    let answer = solve(day, part, &input)
+        .with_context(|| anyhow::anyhow!("failed to solve part {} of day {}", part, day))?;
+    println!("{}", answer);
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use {
+    crate::{math::subset_of_size_summing_to, Solution},
     anyhow::{anyhow, Context},
-    std::convert::TryFrom,
+    serde::Serialize,
+    std::{convert::TryFrom, ops::Deref, str::FromStr},
 };
 
 const SUM_TARGET: u32 = 2020;
@@ -12,175 +14,252 @@ struct Answer {
     product: u32,
 }
 
-fn find_2020_sum_constituents(input: &str, num_entries: usize) -> anyhow::Result<Option<Answer>> {
-    let expense_report_entries = input
+/// Entries of an expense report, in the order they were listed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExpenseReport(Vec<u32>);
+
+impl FromStr for ExpenseReport {
+    type Err = anyhow::Error;
+
+    /// Parses an expense report with one entry per line, ignoring blank lines.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        input
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, l)| {
+                let trimmed = l.trim();
+                if trimmed.is_empty() {
+                    None
+                } else {
+                    Some(trimmed.parse::<u32>().with_context(|| {
+                        anyhow!(
+                            "failed to parse line {} as a number, which is: {:?}",
+                            idx,
+                            l
+                        )
+                    }))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+            .context("failed to parse input")
+    }
+}
+
+/// Like [`ExpenseReport`]'s `FromStr` impl, but ignores anything after a `#` on each line and
+/// also accepts entries separated by commas.
+pub fn parse_entries_lenient(input: &str) -> anyhow::Result<ExpenseReport> {
+    input
         .lines()
         .enumerate()
-        .filter_map(|(idx, l)| {
-            let trimmed = l.trim();
-            if trimmed.is_empty() {
-                None
-            } else {
-                Some(trimmed.parse::<u32>().with_context(|| {
-                    anyhow!(
-                        "failed to parse line {} as a number, which is: {:?}",
-                        idx,
-                        l
-                    )
-                }))
-            }
+        .flat_map(|(idx, l)| {
+            let without_comment = l.find('#').map_or(l, |comment_start| &l[..comment_start]);
+            without_comment
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(move |entry| {
+                    entry.parse::<u32>().with_context(|| {
+                        anyhow!("failed to parse {:?} on line {} as a number", entry, idx)
+                    })
+                })
         })
         .collect::<Result<Vec<_>, _>>()
-        .context("failed to parse input")?;
-    if num_entries > expense_report_entries.len() || num_entries == 0 {
-        return Ok(None);
+        .map(ExpenseReport)
+        .context("failed to parse input")
+}
+
+#[test]
+fn lenient_parsing() {
+    assert_eq!(
+        &*parse_entries_lenient("1721, 979 # noise\n366").unwrap(),
+        [1721, 979, 366],
+    );
+    assert_eq!(
+        &*parse_entries_lenient("# header\n,1,\n\n2 #,3").unwrap(),
+        [1, 2],
+    );
+    assert!(parse_entries_lenient("1721 979").is_err());
+    assert!("1721, 979".parse::<ExpenseReport>().is_err());
+}
+
+impl Deref for ExpenseReport {
+    type Target = [u32];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
+}
 
-    let mut entries_stack: Vec<(usize, u32)> = {
-        let mut entries = Vec::with_capacity(num_entries);
-        entries.extend(
-            expense_report_entries
-                .iter()
-                .copied()
-                .take(num_entries - 1)
-                .enumerate(),
-        );
-        entries
+fn find_2020_sum_constituents(
+    expense_report: &ExpenseReport,
+    num_entries: usize,
+) -> anyhow::Result<Option<Answer>> {
+    let indices = match subset_of_size_summing_to(expense_report, num_entries, SUM_TARGET) {
+        Some(indices) => indices,
+        None => return Ok(None),
     };
 
-    loop {
-        let checked_add = |sum: u32, entry_idx, entry| {
-            let new_sum = sum.checked_add(entry);
-            if new_sum.is_none() {
-                eprintln!(
-                    "warning: addition overflowed for {:?} ({}) + {:?}",
-                    entries_stack,
-                    sum,
-                    (entry_idx, entry)
-                )
-            }
-            new_sum.filter(|&s| s <= SUM_TARGET)
-        };
+    let entries = indices
+        .into_iter()
+        .map(|idx| (idx, expense_report[idx]))
+        .collect::<Vec<_>>();
+    let product = entries
+        .iter()
+        .try_fold(1u32, |product, &(_idx, entry)| product.checked_mul(entry))
+        .with_context(|| {
+            anyhow!(
+                "product of entries {:?} not representable with `u32`",
+                entries
+            )
+        })?;
+    Ok(Some(Answer {
+        entries,
+        sum: SUM_TARGET,
+        product,
+    }))
+}
 
-        if let Some(last_entry) = entries_stack
-            .iter()
-            .copied()
-            .try_fold((0usize, 0u32), |(_idx, sum), (idx, entry)| {
-                checked_add(sum, idx, entry).map(|sum| (idx, sum))
-            })
-            .and_then(|(idx, semifinal_sum)| {
-                expense_report_entries
-                    .iter()
-                    .copied()
-                    .enumerate()
-                    .skip(idx)
-                    .find_map(|(idx, entry)| {
-                        checked_add(semifinal_sum, idx, entry)
-                            .filter(|&sum| sum == SUM_TARGET)
-                            .map(|_sum| (idx, entry))
-                    })
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct Part1Answer {
+    pub e1: (usize, u32),
+    pub e2: (usize, u32),
+    pub sum: u32,
+    pub product: u32,
+}
+
+impl Part1Answer {
+    pub fn find(expense_report: &ExpenseReport) -> anyhow::Result<Self> {
+        find_2020_sum_constituents(expense_report, 2)
+            .and_then(|ans| {
+                ans.with_context(|| {
+                    anyhow!("failed to find entry pair that sums to {}", SUM_TARGET)
+                })
             })
-        {
-            entries_stack.push(last_entry);
-            break Ok(Some(Answer {
-                product: entries_stack
-                    .iter()
-                    .copied()
-                    .fold(1, |product: u32, (_idx, entry)| -> u32 {
-                        product.checked_mul(entry).unwrap()
-                    }),
-                entries: entries_stack,
-                sum: SUM_TARGET,
-            }));
-        }
-
-        match entries_stack
-            .iter()
-            .copied()
-            .map(|(idx, _entry)| idx)
-            .enumerate()
-            .rev()
-            .zip(1..)
-            .find_map(|((stack_idx, entry_idx), num_digits_carried)| {
-                if num_digits_carried + entry_idx < expense_report_entries.len() {
-                    Some((stack_idx, entry_idx))
-                } else {
-                    None
-                }
-            }) {
-            None => break Ok(None),
-            Some((stack_idx, entry_idx)) => {
-                entries_stack.iter_mut().skip(stack_idx).zip(1..).for_each(
-                    |(stack_entry, offset)| {
-                        let new_entry_idx = entry_idx + offset;
-                        *stack_entry = (new_entry_idx, expense_report_entries[new_entry_idx]);
-                    },
-                );
-            }
-        }
+            .map(
+                |Answer {
+                     entries,
+                     sum,
+                     product,
+                 }| {
+                    let [e1, e2] = <[(usize, u32); 2]>::try_from(entries).unwrap();
+                    Part1Answer {
+                        e1,
+                        e2,
+                        sum,
+                        product,
+                    }
+                },
+            )
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-struct Part1Answer {
-    e1: (usize, u32),
-    e2: (usize, u32),
-    sum: u32,
-    product: u32,
+pub fn part_1(input: &str) -> anyhow::Result<Part1Answer> {
+    Part1Answer::find(&input.parse()?)
 }
 
-fn part_1(input: &str) -> anyhow::Result<Part1Answer> {
-    find_2020_sum_constituents(input, 2)
-        .and_then(|ans| {
-            ans.with_context(|| anyhow!("failed to find entry pair that sums to {}", SUM_TARGET))
-        })
-        .map(
-            |Answer {
-                 entries,
-                 sum,
-                 product,
-             }| {
-                let [e1, e2] = <[(usize, u32); 2]>::try_from(entries).unwrap();
-                Part1Answer {
-                    e1,
-                    e2,
-                    sum,
-                    product,
-                }
-            },
-        )
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct Part2Answer {
+    pub e1: (usize, u32),
+    pub e2: (usize, u32),
+    pub e3: (usize, u32),
+    pub sum: u32,
+    pub product: u32,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-struct Part2Answer {
-    e1: (usize, u32),
-    e2: (usize, u32),
-    e3: (usize, u32),
-    sum: u32,
-    product: u32,
+impl Part2Answer {
+    pub fn find(expense_report: &ExpenseReport) -> anyhow::Result<Self> {
+        find_2020_sum_constituents(expense_report, 3)
+            .and_then(|ans| {
+                ans.with_context(|| {
+                    anyhow!("failed to find entry triplet that sums to {}", SUM_TARGET)
+                })
+            })
+            .map(
+                |Answer {
+                     entries,
+                     sum,
+                     product,
+                 }| {
+                    let [e1, e2, e3] = <[(usize, u32); 3]>::try_from(entries).unwrap();
+                    Part2Answer {
+                        e1,
+                        e2,
+                        e3,
+                        sum,
+                        product,
+                    }
+                },
+            )
+    }
 }
 
-fn part_2(input: &str) -> anyhow::Result<Part2Answer> {
-    find_2020_sum_constituents(input, 3)
-        .and_then(|ans| {
-            ans.with_context(|| anyhow!("failed to find entry triplet that sums to {}", SUM_TARGET))
-        })
-        .map(
-            |Answer {
-                 entries,
-                 sum,
-                 product,
-             }| {
-                let [e1, e2, e3] = <[(usize, u32); 3]>::try_from(entries).unwrap();
-                Part2Answer {
-                    e1,
-                    e2,
-                    e3,
-                    sum,
-                    product,
+pub fn part_2(input: &str) -> anyhow::Result<Part2Answer> {
+    Part2Answer::find(&input.parse()?)
+}
+
+/// Like [`part_2`], but sorts the entries and then, for each entry, scans the remaining ones from
+/// both ends for a pair completing the sum, which is `O(n²)` rather than `O(n³)`.
+pub fn part_2_fast(input: &str) -> anyhow::Result<Part2Answer> {
+    let mut entries = input
+        .parse::<ExpenseReport>()?
+        .iter()
+        .copied()
+        .enumerate()
+        .collect::<Vec<_>>();
+    entries.sort_unstable_by_key(|&(_idx, entry)| entry);
+
+    let mut found = entries
+        .iter()
+        .enumerate()
+        .find_map(|(sorted_idx, &first)| {
+            let remainder = SUM_TARGET.checked_sub(first.1)?;
+            let rest = &entries[sorted_idx + 1..];
+            let (mut low, mut high) = (0, rest.len().checked_sub(1)?);
+            while low < high {
+                let pair_sum = u64::from(rest[low].1) + u64::from(rest[high].1);
+                if pair_sum < u64::from(remainder) {
+                    low += 1;
+                } else if pair_sum > u64::from(remainder) {
+                    high -= 1;
+                } else {
+                    return Some([first, rest[low], rest[high]]);
                 }
-            },
-        )
+            }
+            None
+        })
+        .with_context(|| anyhow!("failed to find entry triplet that sums to {}", SUM_TARGET))?;
+    found.sort_unstable_by_key(|&(idx, _entry)| idx);
+
+    let [e1, e2, e3] = found;
+    Ok(Part2Answer {
+        e1,
+        e2,
+        e3,
+        sum: SUM_TARGET,
+        product: found
+            .iter()
+            .try_fold(1u32, |product, &(_idx, entry)| product.checked_mul(entry))
+            .context("product of entry triplet not representable with `u32`")?,
+    })
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    type Parsed = ExpenseReport;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Parsed> {
+        input.parse()
+    }
+
+    fn part_1(expense_report: &Self::Parsed) -> anyhow::Result<String> {
+        Part1Answer::find(expense_report).map(|answer| answer.product.to_string())
+    }
+
+    fn part_2(expense_report: &Self::Parsed) -> anyhow::Result<String> {
+        Part2Answer::find(expense_report).map(|answer| answer.product.to_string())
+    }
 }
 
 const EXAMPLE: &str = "
@@ -206,6 +285,17 @@ fn d01p1_sample() {
     );
 }
 
+#[test]
+fn expense_report_reuse() {
+    let expense_report = EXAMPLE.parse::<ExpenseReport>().unwrap();
+    assert_eq!(&*expense_report, [1721, 979, 366, 299, 675, 1456]);
+    assert_eq!(Part1Answer::find(&expense_report).unwrap().product, 514579);
+    assert_eq!(
+        Part2Answer::find(&expense_report).unwrap().product,
+        241861950
+    );
+}
+
 #[test]
 fn d01p1_answer() {
     assert_eq!(
@@ -246,3 +336,27 @@ fn d01p2_answer() {
         },
     );
 }
+
+#[test]
+fn solve_json_sample() {
+    let answer = crate::days::solve_json(1, 1, EXAMPLE).unwrap();
+    assert_eq!(answer["product"], 514579);
+    assert_eq!(answer["e1"], serde_json::json!([0, 1721]));
+    assert_eq!(
+        crate::days::solve_json(2, 1, "1-3 a: abcde\n").unwrap(),
+        "1"
+    );
+}
+
+#[test]
+fn solve_sample() {
+    assert_eq!(crate::days::solve(1, 1, EXAMPLE).unwrap(), "514579");
+    assert_eq!(crate::days::solve(1, 2, EXAMPLE).unwrap(), "241861950");
+}
+
+#[test]
+fn d01p2_fast() {
+    [EXAMPLE, INPUT].iter().for_each(|input| {
+        assert_eq!(part_2_fast(input).unwrap(), part_2(input).unwrap());
+    });
+}
@@ -1,6 +1,6 @@
 use {
     crate::parsing::lines_without_endings,
-    anyhow::{anyhow, Context},
+    anyhow::{anyhow, ensure, Context},
     itertools::Itertools,
     re_parse::ReParse,
     regex::Regex, // FIXME: file an upstream PR to get rid of the need for this
@@ -8,7 +8,8 @@ use {
     std::{borrow::Cow, convert::TryInto, num::NonZeroUsize, ops::RangeInclusive},
 };
 
-trait PasswordPolicy
+/// A password policy that can be parsed from the `<lower>-<upper> <char>` portion of a line.
+pub trait PasswordPolicy
 where
     Self: Sized,
 {
@@ -21,7 +22,7 @@ where
 /// ```txt
 /// <lower>-<upper> <char>: <password>
 /// ```
-fn parse_policy_password_pair<T>(s: &str) -> anyhow::Result<(T, Cow<'_, str>)>
+pub fn parse_policy_password_pair<T>(s: &str) -> anyhow::Result<(T, Cow<'_, str>)>
 where
     T: PasswordPolicy,
 {
@@ -49,15 +50,60 @@ where
     Ok((concrete_policy, password))
 }
 
+/// Like [`parse_policy_password_pair`], but parses by hand rather than with a regular expression.
+pub fn parse_policy_password_pair_manual<T>(s: &str) -> anyhow::Result<(T, Cow<'_, str>)>
+where
+    T: PasswordPolicy,
+{
+    let parse_bound = |bound_name, raw: Option<&str>| -> anyhow::Result<u8> {
+        let raw = raw.with_context(|| anyhow!("missing {} bound", bound_name))?;
+        ensure!(
+            !raw.is_empty() && raw.bytes().all(|b| b.is_ascii_digit()),
+            "expected {} bound to be digits, but got {:?}",
+            bound_name,
+            raw,
+        );
+        raw.parse()
+            .with_context(|| anyhow!("failed to parse {} bound", bound_name))
+    };
+
+    ensure!(!s.contains('\n'), "expected a single line");
+
+    let mut policy_and_rest = s.splitn(2, ' ');
+    let mut bounds = policy_and_rest.next().unwrap().splitn(2, '-');
+    let lower = parse_bound("lower", bounds.next())?;
+    let upper = parse_bound("upper", bounds.next())?;
+
+    let mut rest = policy_and_rest
+        .next()
+        .context("expected a space after the bounds")?
+        .chars();
+    let character = rest.next().context("missing policy character")?;
+    let password = rest
+        .as_str()
+        .strip_prefix(": ")
+        .context("expected `: ` after the policy character")?;
+
+    let concrete_policy = T::from_raw(lower, upper, character)
+        .context("parse succeeded, but conversion to concrete policy failed")?;
+
+    Ok((concrete_policy, Cow::Borrowed(password)))
+}
+
 #[derive(Debug, Eq, PartialEq)]
-struct MisrememberedPasswordPolicy {
+pub struct MisrememberedPasswordPolicy {
     range: RangeInclusive<u8>,
     character: char,
 }
 
 impl PasswordPolicy for MisrememberedPasswordPolicy {
     fn from_raw(lower: u8, upper: u8, character: char) -> anyhow::Result<Self> {
-        // TODO: There's some error surface not getting modeled here yet. Ew.
+        ensure!(
+            lower <= upper,
+            "lower bound {} is greater than upper bound {}; no count would be valid",
+            lower,
+            upper,
+        );
         Ok(Self {
             range: RangeInclusive::new(lower, upper),
             character,
@@ -81,15 +127,44 @@ impl PasswordPolicy for MisrememberedPasswordPolicy {
     }
 }
 
-fn parse_password_policy_lines<T>(
+pub fn parse_password_policy_lines<T>(
     s: &str,
 ) -> impl Iterator<Item = anyhow::Result<(T, Cow<'_, str>)>>
 where
     T: PasswordPolicy,
 {
     lines_without_endings(s)
-        .filter(|l| !l.is_empty())
-        .map(parse_policy_password_pair)
+        .zip(1..)
+        .filter(|(l, _line_num)| !l.is_empty())
+        .map(|(l, line_num)| {
+            parse_policy_password_pair_manual(l)
+                .with_context(|| anyhow!("failed to parse line {}", line_num))
+        })
+}
+
+fn count_valid_passwords_strict<T>(s: &str) -> anyhow::Result<usize>
+where
+    T: PasswordPolicy,
+{
+    parse_password_policy_lines::<T>(s).try_fold(0, |count, res| {
+        let (pol, pw) = res?;
+        Ok(if pol.validate(&pw) { count + 1 } else { count })
+    })
+}
+
+/// Policy-password pairs that own their passwords.
+pub type OwnedPairs<T> = Vec<(T, String)>;
+
+/// Splits the policy-password pairs in `s` into those whose password is valid under its policy
+/// and those whose password isn't, failing on the first line that can't be parsed.
+pub fn partition_passwords<T>(s: &str) -> anyhow::Result<(OwnedPairs<T>, OwnedPairs<T>)>
+where
+    T: PasswordPolicy,
+{
+    let pairs = parse_password_policy_lines::<T>(s)
+        .map(|res| res.map(|(pol, pw)| (pol, pw.into_owned())))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(pairs.into_iter().partition(|(pol, pw)| pol.validate(pw)))
 }
 
 fn part_1(s: &str) -> usize {
@@ -99,8 +174,13 @@ fn part_1(s: &str) -> usize {
         .count()
 }
 
+/// Like [`part_1`], but fails on the first line that can't be parsed instead of skipping it.
+pub fn part_1_strict(s: &str) -> anyhow::Result<usize> {
+    count_valid_passwords_strict::<MisrememberedPasswordPolicy>(s)
+}
+
 #[derive(Debug, Eq, PartialEq)]
-struct ActualPasswordPolicy {
+pub struct ActualPasswordPolicy {
     positions: [NonZeroUsize; 2],
     character: char,
 }
@@ -148,6 +228,49 @@ fn part_2(s: &str) -> usize {
         .count()
 }
 
+/// Like [`part_2`], but fails on the first line that can't be parsed instead of skipping it.
+pub fn part_2_strict(s: &str) -> anyhow::Result<usize> {
+    count_valid_passwords_strict::<ActualPasswordPolicy>(s)
+}
+
+/// Like [`ActualPasswordPolicy`], but `lower` and `upper` are 0-based byte offsets into the
+/// password instead of 1-based character positions.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ExactlyOnceEitherPolicy {
+    offsets: [usize; 2],
+    character: char,
+}
+
+impl PasswordPolicy for ExactlyOnceEitherPolicy {
+    fn from_raw(lower: u8, upper: u8, character: char) -> anyhow::Result<Self> {
+        Ok(Self {
+            offsets: [usize::from(lower), usize::from(upper)],
+            character,
+        })
+    }
+
+    fn validate(&self, password: &str) -> bool {
+        let Self { offsets, character } = self;
+
+        offsets
+            .iter()
+            .filter(|&&offset| {
+                password
+                    .get(offset..)
+                    .map_or(false, |rest| rest.starts_with(*character))
+            })
+            .count()
+            == 1
+    }
+}
+
+fn part_3(s: &str) -> usize {
+    parse_password_policy_lines::<ExactlyOnceEitherPolicy>(s)
+        .filter_map(|res| res.ok())
+        .filter(|(pol, pw)| pol.validate(&pw))
+        .count()
+}
+
 const SAMPLE: &str = "\
 1-3 a: abcde
 1-3 b: cdefg
@@ -174,6 +297,75 @@ fn p1_sample() {
     assert_eq!(part_1(SAMPLE), 2);
 }
 
+#[test]
+fn multibyte_policy_character() {
+    let validate_line = |l: &str| {
+        let (misremembered, pw) =
+            parse_policy_password_pair::<MisrememberedPasswordPolicy>(l).unwrap();
+        let (actual, _pw) = parse_policy_password_pair::<ActualPasswordPolicy>(l).unwrap();
+        (misremembered.validate(&pw), actual.validate(&pw))
+    };
+
+    assert_eq!(validate_line("1-3 é: ééabc"), (true, true));
+    assert_eq!(validate_line("1-2 é: éé"), (true, false));
+    assert_eq!(validate_line("2-3 é: aébé"), (true, true));
+    assert_eq!(validate_line("3-4 é: éaée"), (false, true));
+}
+
+#[test]
+fn sample_partitions() {
+    let (valid, invalid) = partition_passwords::<MisrememberedPasswordPolicy>(SAMPLE).unwrap();
+    assert_eq!((valid.len(), invalid.len()), (2, 1));
+    assert_eq!(invalid[0].1, "cdefg");
+
+    let (valid, invalid) = partition_passwords::<ActualPasswordPolicy>(SAMPLE).unwrap();
+    assert_eq!((valid.len(), invalid.len()), (1, 2));
+
+    assert!(partition_passwords::<MisrememberedPasswordPolicy>("1-3 a abcde").is_err());
+}
+
+#[test]
+fn manual_parsing_agrees() {
+    #[track_caller]
+    fn assert_agreement<T>(l: &str)
+    where
+        T: PasswordPolicy + std::fmt::Debug + PartialEq,
+    {
+        assert_eq!(
+            parse_policy_password_pair_manual::<T>(l).ok(),
+            parse_policy_password_pair::<T>(l).ok(),
+            "parsers disagree on {:?}",
+            l,
+        );
+    }
+
+    lines_without_endings(SAMPLE)
+        .chain(lines_without_endings(INPUT))
+        .chain(
+            [
+                "1-3 :: a: b",
+                "1-3 a:abc",
+                "1-3 a: ",
+                "1-3 é: éé",
+                "+1-3 a: abc",
+                "1-256 a: abc",
+                "1-3a: abc",
+                "1- a: abc",
+                "1-3 : : a",
+                "1-3  : a",
+                "",
+            ]
+            .iter()
+            .copied(),
+        )
+        .for_each(|l| {
+            assert_agreement::<MisrememberedPasswordPolicy>(l);
+            assert_agreement::<ActualPasswordPolicy>(l);
+            assert_agreement::<ExactlyOnceEitherPolicy>(l);
+        });
+    assert!(parse_policy_password_pair_manual::<MisrememberedPasswordPolicy>("1-3 a: abc").is_ok());
+}
+
 #[test]
 fn p1_answer() {
     assert_eq!(part_1(INPUT), 603);
@@ -210,3 +402,54 @@ fn p2_sample() {
 fn p2_answer() {
     assert_eq!(part_2(INPUT), 404);
 }
+
+#[test]
+fn p3_sample() {
+    assert_eq!(
+        parse_password_policy_lines::<ExactlyOnceEitherPolicy>(SAMPLE)
+            .filter_map(|res| res.ok())
+            .filter(|(pol, pw)| pol.validate(&pw))
+            .collect_tuple::<(_,)>(),
+        Some(((
+            ExactlyOnceEitherPolicy {
+                offsets: [2, 9],
+                character: 'c',
+            },
+            "ccccccccc".into(),
+        ),)),
+    );
+    // Same count as part 2, but for a different line: part 2 accepts `1-3 a: abcde` instead.
+    assert_eq!(part_3(SAMPLE), 1);
+    assert_eq!(part_2(SAMPLE), 1);
+
+    // 0 is a valid offset here, but not a valid position for part 2.
+    let zero_offset = "0-2 a: abc\n";
+    assert_eq!(part_3(zero_offset), 1);
+    assert_eq!(part_2(zero_offset), 0);
+}
+
+#[test]
+fn p1_inverted_range() {
+    let err = parse_policy_password_pair::<MisrememberedPasswordPolicy>("3-1 a: abc").unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("lower bound 3 is greater than upper bound 1"),
+        "unexpected error: {:?}",
+        err,
+    );
+}
+
+#[test]
+fn strict_parsing() {
+    let with_garbage = format!("{}garbage\n", SAMPLE);
+
+    assert_eq!(part_1(&with_garbage), 2);
+    assert_eq!(part_2(&with_garbage), 1);
+
+    let err = part_1_strict(&with_garbage).unwrap_err();
+    assert_eq!(err.to_string(), "failed to parse line 4");
+    let err = part_2_strict(&with_garbage).unwrap_err();
+    assert_eq!(err.to_string(), "failed to parse line 4");
+
+    assert_eq!(part_1_strict(SAMPLE).unwrap(), 2);
+    assert_eq!(part_2_strict(SAMPLE).unwrap(), 1);
+}
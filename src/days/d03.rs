@@ -2,7 +2,11 @@ use {
     crate::parsing::lines_without_endings,
     anyhow::{anyhow, ensure, Context},
     itertools::Itertools,
-    std::{iter::once, num::NonZeroUsize},
+    std::{
+        fmt::{self, Display, Formatter},
+        iter::{once, repeat_with, successors},
+        num::NonZeroUsize,
+    },
 };
 
 const SAMPLE: &str = "\
@@ -21,14 +25,33 @@ const SAMPLE: &str = "\
 
 const INPUT: &str = include_str!("d03.txt");
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 enum TobogganAreaTile {
     OpenSquare,
     Tree,
 }
 
+impl TobogganAreaTile {
+    const OPEN_SQUARE: char = '.';
+    const TREE: char = '#';
+
+    fn as_char(self) -> char {
+        match self {
+            Self::OpenSquare => Self::OPEN_SQUARE,
+            Self::Tree => Self::TREE,
+        }
+    }
+}
+
+impl Display for TobogganAreaTile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_char())
+    }
+}
+
+/// A map of open squares and trees, whose pattern repeats infinitely to the right.
 #[derive(Debug, Clone)]
-struct TobogganArea {
+pub struct TobogganArea {
     definition_width: usize,
     tiles: Vec<TobogganAreaTile>,
 }
@@ -40,9 +63,24 @@ struct TobogganSlope {
 }
 
 impl TobogganArea {
-    fn new(s: &str) -> anyhow::Result<Self> {
+    /// Parses an area with one row of tiles per line, failing if lines differ in length.
+    pub fn new(s: &str) -> anyhow::Result<Self> {
+        Self::parse(s, false)
+    }
+
+    /// Like [`Self::new`], but right-pads lines shorter than the first with open squares instead
+    /// of failing. Lines longer than the first are still an error.
+    pub fn new_lenient(s: &str) -> anyhow::Result<Self> {
+        Self::parse(s, true)
+    }
+
+    fn parse(s: &str, pad_short_lines: bool) -> anyhow::Result<Self> {
         let mut lines = lines_without_endings(s);
-        let (first_line,) = lines.by_ref().take(1).collect_tuple().unwrap();
+        let (first_line,) = lines
+            .by_ref()
+            .take(1)
+            .collect_tuple()
+            .context("map is empty")?;
 
         ensure!(
             !first_line.is_empty(),
@@ -56,12 +94,18 @@ impl TobogganArea {
             .zip(1..)
             .flat_map(|(l, one_based_line_idx)| {
                 let line_err_ctx = move || anyhow!("failed to parse line {}", one_based_line_idx);
-                if l.len() != expected_line_len {
+                let padding_len = if pad_short_lines {
+                    expected_line_len.saturating_sub(l.len())
+                } else {
+                    0
+                };
+                if l.len() + padding_len != expected_line_len {
                     Some(
                         Err(anyhow!(
-                            "expected line to be of len {}, but it was of len {}",
+                            "expected line to be of len {}, but it was of len {}: {:?}",
                             expected_line_len,
                             l.len(),
+                            l,
                         ))
                         .with_context(line_err_ctx),
                     )
@@ -71,14 +115,12 @@ impl TobogganArea {
                 .into_iter()
                 .chain(l.char_indices().zip(1..).take(expected_line_len).map(
                     move |((zero_based_char_byte_idx, c), one_based_col)| {
-                        const OPEN_SQUARE: char = '.';
-                        const TREE: char = '#';
                         match c {
-                            OPEN_SQUARE => Ok(TobogganAreaTile::OpenSquare),
-                            TREE => Ok(TobogganAreaTile::Tree),
+                            TobogganAreaTile::OPEN_SQUARE => Ok(TobogganAreaTile::OpenSquare),
+                            TobogganAreaTile::TREE => Ok(TobogganAreaTile::Tree),
                             c => Err(anyhow!(
                                 "expected one of {:?}, got {:?} at column {} (byte {})",
-                                [OPEN_SQUARE, TREE],
+                                [TobogganAreaTile::OPEN_SQUARE, TobogganAreaTile::TREE],
                                 c,
                                 one_based_col,
                                 zero_based_char_byte_idx,
@@ -87,6 +129,7 @@ impl TobogganArea {
                         }
                     },
                 ))
+                .chain(repeat_with(|| Ok(TobogganAreaTile::OpenSquare)).take(padding_len))
             })
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -96,6 +139,15 @@ impl TobogganArea {
         })
     }
 
+    /// Returns the `(width, height)` of this area's definition.
+    pub fn dimensions(&self) -> (usize, usize) {
+        let &Self {
+            definition_width,
+            ref tiles,
+        } = self;
+        (definition_width, tiles.len() / definition_width)
+    }
+
     fn iter_slope_tiles(
         &self,
         slope: TobogganSlope,
@@ -134,6 +186,8 @@ impl TobogganArea {
             // NOTE(erichdongubler): I'm actually not sure if it'd be easier/faster to just
             // manipulate logical coordinates that then get translated into a single new offset,
             // instead of trying to fancily recalculate the offset like we are doing here.
+            // `iter_slope_tiles_by_coords` does the former, and `logical_coords_match_offset_math`
+            // checks that both agree.
             let new_logical_vert_pos = current_logical_vert_pos + logical_vert_step;
             let new_pos = {
                 let horiz_adjusted_pos = horiz_step.checked_add(current_pos)?;
@@ -156,18 +210,181 @@ impl TobogganArea {
             Some(tile)
         }))
     }
+
+    /// Counts the trees encountered when travelling down `slope` from the top-left corner.
+    fn count_trees_on_slope(&self, slope: TobogganSlope) -> anyhow::Result<usize> {
+        Ok(self
+            .iter_slope_tiles(slope)?
+            .filter(|t| matches!(t, TobogganAreaTile::Tree))
+            .count())
+    }
+
+    /// Like [`Self::iter_slope_tiles`], but tracks position as logical `(x, y)` coordinates,
+    /// wrapping horizontally with `x % width`, instead of recalculating a flat offset.
+    fn iter_slope_tiles_by_coords(
+        &self,
+        slope: TobogganSlope,
+    ) -> impl Iterator<Item = TobogganAreaTile> + '_ {
+        let &Self {
+            ref tiles,
+            definition_width,
+        } = self;
+        let TobogganSlope {
+            horiz_step,
+            vert_step,
+        } = slope;
+        let height = tiles.len() / definition_width;
+
+        successors(Some((0usize, 0usize)), move |&(x, y)| {
+            Some((
+                x.checked_add(horiz_step.get())? % definition_width,
+                y.checked_add(vert_step.get())?,
+            ))
+        })
+        .skip(1)
+        .take_while(move |&(_x, y)| y < height)
+        .map(move |(x, y)| tiles[y * definition_width + x])
+    }
+}
+
+/// Glyphs to render each kind of tile in a [`TobogganArea`] with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TobogganTileChars {
+    pub open_square: char,
+    pub tree: char,
+}
+
+impl TobogganTileChars {
+    /// The glyphs used by puzzle input, and by [`TobogganArea`]'s `Display` impl.
+    pub const CANONICAL: Self = Self {
+        open_square: TobogganAreaTile::OPEN_SQUARE,
+        tree: TobogganAreaTile::TREE,
+    };
+
+    fn get(self, tile: TobogganAreaTile) -> char {
+        match tile {
+            TobogganAreaTile::OpenSquare => self.open_square,
+            TobogganAreaTile::Tree => self.tree,
+        }
+    }
+}
+
+impl Default for TobogganTileChars {
+    fn default() -> Self {
+        Self::CANONICAL
+    }
+}
+
+impl TobogganArea {
+    /// Renders this area like its `Display` impl does, but with glyphs from `chars`.
+    pub fn render_with(&self, chars: TobogganTileChars) -> String {
+        let mut rendered = String::new();
+        self.write_with(&mut rendered, chars).unwrap();
+        rendered
+    }
+
+    fn write_with<W>(&self, w: &mut W, chars: TobogganTileChars) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let &Self {
+            ref tiles,
+            definition_width,
+        } = self;
+
+        tiles.chunks(definition_width).try_for_each(|chunk| {
+            chunk
+                .iter()
+                .try_for_each(|&tile| w.write_char(chars.get(tile)))?;
+            writeln!(w)
+        })
+    }
+}
+
+impl Display for TobogganArea {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_with(f, TobogganTileChars::CANONICAL)
+    }
+}
+
+#[test]
+fn render_with_custom_glyphs() {
+    let area = TobogganArea::new("..#\n#..\n").unwrap();
+    assert_eq!(
+        area.render_with(TobogganTileChars {
+            open_square: '_',
+            tree: 'T',
+        }),
+        "__T\nT__\n",
+    );
+    assert_eq!(
+        area.render_with(TobogganTileChars::default()),
+        area.to_string()
+    );
+}
+
+#[test]
+fn display_round_trip() {
+    let area = TobogganArea::new(SAMPLE).unwrap();
+    assert_eq!(area.dimensions(), (11, 11));
+
+    let displayed = area.to_string();
+    assert_eq!(displayed, SAMPLE);
+    assert_eq!(TobogganArea::new(&displayed).unwrap().tiles, area.tiles);
+}
+
+#[test]
+fn ragged_lines() {
+    let short_line = "\
+#.#
+#
+..#
+";
+    let err = TobogganArea::new(short_line).unwrap_err();
+    assert_eq!(
+        format!("{:#}", err),
+        "failed to parse line 2: expected line to be of len 3, but it was of len 1: \"#\"",
+    );
+    let area = TobogganArea::new_lenient(short_line).unwrap();
+    assert_eq!(area.dimensions(), (3, 3));
+    assert_eq!(area.to_string(), "#.#\n#..\n..#\n");
+
+    let long_line = "\
+#.#
+#.# 
+..#
+";
+    [
+        TobogganArea::new(long_line),
+        TobogganArea::new_lenient(long_line),
+    ]
+    .iter()
+    .for_each(|res| {
+        assert_eq!(
+            format!("{:#}", res.as_ref().unwrap_err()),
+            "failed to parse line 2: expected line to be of len 3, but it was of len 4: \"#.# \"",
+        );
+    });
+}
+
+#[test]
+fn empty_area() {
+    assert_eq!(
+        TobogganArea::new("").unwrap_err().to_string(),
+        "map is empty"
+    );
+    assert_eq!(
+        format!("{:#}", part_1("").unwrap_err()),
+        "failed to parse toboggan area: map is empty",
+    );
 }
 
-fn part_1(s: &str) -> anyhow::Result<usize> {
+pub fn part_1(s: &str) -> anyhow::Result<usize> {
     let area = TobogganArea::new(s).context("failed to parse toboggan area")?;
-    let tiles = area.iter_slope_tiles(TobogganSlope {
+    area.count_trees_on_slope(TobogganSlope {
         horiz_step: NonZeroUsize::new(3).unwrap(),
         vert_step: NonZeroUsize::new(1).unwrap(),
-    })?;
-    let trees_touched = tiles
-        .filter(|t| matches!(t, TobogganAreaTile::Tree))
-        .count();
-    Ok(trees_touched)
+    })
 }
 
 #[test]
@@ -176,12 +393,46 @@ fn p1_sample() {
     assert_eq!(part_1(SAMPLE).unwrap(), 7);
 }
 
+#[test]
+fn count_trees_on_sample_slope() {
+    let area = TobogganArea::new(SAMPLE).unwrap();
+    assert_eq!(
+        area.count_trees_on_slope(TobogganSlope {
+            horiz_step: NonZeroUsize::new(3).unwrap(),
+            vert_step: NonZeroUsize::new(1).unwrap(),
+        })
+        .unwrap(),
+        7,
+    );
+}
+
+#[test]
+fn logical_coords_match_offset_math() {
+    let area = TobogganArea::new(SAMPLE).unwrap();
+    [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2), (2, 3), (10, 1)]
+        .iter()
+        .cloned()
+        .for_each(|(right, down)| {
+            let slope = || TobogganSlope {
+                horiz_step: NonZeroUsize::new(right).unwrap(),
+                vert_step: NonZeroUsize::new(down).unwrap(),
+            };
+            assert_eq!(
+                area.iter_slope_tiles(slope()).unwrap().collect::<Vec<_>>(),
+                area.iter_slope_tiles_by_coords(slope()).collect::<Vec<_>>(),
+                "tile sequences diverged for slope ({}, {})",
+                right,
+                down,
+            );
+        });
+}
+
 #[test]
 fn p1_answer() {
     assert_eq!(part_1(INPUT).unwrap(), 184);
 }
 
-fn part_2(s: &str) -> anyhow::Result<usize> {
+pub fn part_2(s: &str) -> anyhow::Result<usize> {
     let area = TobogganArea::new(s).context("failed to parse toboggan area")?;
     [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
         .iter()
@@ -189,13 +440,10 @@ fn part_2(s: &str) -> anyhow::Result<usize> {
         .try_fold(
             1,
             |trees_encountered_product, (right, down)| -> anyhow::Result<_> {
-                let tiles = area.iter_slope_tiles(TobogganSlope {
+                let trees_touched = area.count_trees_on_slope(TobogganSlope {
                     horiz_step: NonZeroUsize::new(right).unwrap(),
                     vert_step: NonZeroUsize::new(down).unwrap(),
                 })?;
-                let trees_touched = tiles
-                    .filter(|t| matches!(t, TobogganAreaTile::Tree))
-                    .count();
 
                 let trees_encountered_product = trees_touched
                     .checked_mul(trees_encountered_product)
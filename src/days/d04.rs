@@ -1,8 +1,16 @@
 use {
-    anyhow::{anyhow, Context},
+    crate::parsing::records,
+    anyhow::{anyhow, bail, ensure, Context},
     itertools::Itertools,
     serde::Deserialize,
     serde_json::{Map, Value as JsonValue},
+    std::{
+        convert::{TryFrom, TryInto},
+        fmt::{self, Display, Formatter},
+        num::ParseIntError,
+        ops::RangeInclusive,
+        str::FromStr,
+    },
 };
 
 const SAMPLE: &str = "\
@@ -26,7 +34,7 @@ const INPUT: &str = include_str!("d04.txt");
 fn parse_key_value_records(
     s: &str,
 ) -> impl Iterator<Item = anyhow::Result<Map<String, JsonValue>>> + '_ {
-    s.split("\n\n").map(|e| {
+    records(s).map(|e| {
         e.split_whitespace()
             .map(|kv| {
                 kv.splitn(2, ':')
@@ -39,39 +47,237 @@ fn parse_key_value_records(
 }
 
 #[derive(Debug, Deserialize)]
-struct RawCommonIdentityFields {
+pub struct RawCommonIdentityFields {
     #[serde(rename = "byr")]
-    birth_year: String,
+    pub birth_year: String,
     #[serde(rename = "iyr")]
-    issue_year: String,
+    pub issue_year: String,
     #[serde(rename = "eyr")]
-    expiration_year: String,
+    pub expiration_year: String,
     #[serde(rename = "hgt")]
-    height: String,
+    pub height: String,
     #[serde(rename = "hcl")]
-    hair_color: String,
+    pub hair_color: String,
     #[serde(rename = "ecl")]
-    eye_color: String,
+    pub eye_color: String,
     #[serde(rename = "pid")]
-    passport_id: String,
+    pub passport_id: String,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
-enum RawIdentity {
-    NorthPoleCredentials(RawCommonIdentityFields),
+pub enum RawIdentity {
+    // NOTE: `Passport` needs to come first, since `untagged` tries variants in order and
+    // `NorthPoleCredentials` would otherwise happily ignore the `cid` field.
     Passport {
         #[serde(rename = "cid")]
         country_id: String,
         #[serde(flatten)]
         common: RawCommonIdentityFields,
     },
+    NorthPoleCredentials(RawCommonIdentityFields),
 }
 
 fn parse_identity_record(map: Map<String, JsonValue>) -> anyhow::Result<RawIdentity> {
     serde_json::from_value(JsonValue::Object(map)).context("failed to parse identity document")
 }
 
+pub fn parse_identities(s: &str) -> impl Iterator<Item = anyhow::Result<RawIdentity>> + '_ {
+    parse_key_value_records(s).map(|res| res.and_then(parse_identity_record))
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Height {
+    Cm(u8),
+    In(u8),
+}
+
+/// A failure to parse a [`Height`].
+#[derive(Debug, thiserror::Error)]
+pub enum HeightParseError {
+    #[error("expected height to end with \"cm\" or \"in\", got {0:?}")]
+    UnknownUnit(String),
+    #[error("failed to parse height value {value:?}")]
+    Malformed {
+        value: String,
+        source: ParseIntError,
+    },
+}
+
+impl FromStr for Height {
+    type Err = HeightParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (raw_value, unit): (_, fn(u8) -> Self) = if let Some(cm) = s.strip_suffix("cm") {
+            (cm, Self::Cm)
+        } else if let Some(ins) = s.strip_suffix("in") {
+            (ins, Self::In)
+        } else {
+            return Err(HeightParseError::UnknownUnit(s.to_owned()));
+        };
+        raw_value
+            .parse::<u8>()
+            .map(unit)
+            .map_err(|source| HeightParseError::Malformed {
+                value: raw_value.to_owned(),
+                source,
+            })
+    }
+}
+
+impl Height {
+    pub fn in_valid_range(&self) -> bool {
+        match *self {
+            Self::Cm(cm) => (150..=193).contains(&cm),
+            Self::In(ins) => (59..=76).contains(&ins),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EyeColor {
+    Amb,
+    Blu,
+    Brn,
+    Gry,
+    Grn,
+    Hzl,
+    Oth,
+}
+
+impl FromStr for EyeColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "amb" => Self::Amb,
+            "blu" => Self::Blu,
+            "brn" => Self::Brn,
+            "gry" => Self::Gry,
+            "grn" => Self::Grn,
+            "hzl" => Self::Hzl,
+            "oth" => Self::Oth,
+            _ => bail!("unrecognized eye color {:?}", s),
+        })
+    }
+}
+
+#[test]
+fn eye_color_parsing() {
+    assert_eq!("brn".parse::<EyeColor>().unwrap(), EyeColor::Brn);
+    assert!("wat".parse::<EyeColor>().is_err());
+}
+
+/// A passport ID of exactly nine digits, including any leading zeroes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PassportId([u8; 9]);
+
+impl FromStr for PassportId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ensure!(
+            s.len() == 9,
+            "expected passport ID to have 9 digits, got {:?}",
+            s
+        );
+        let mut digits = [0; 9];
+        for (digit, c) in digits.iter_mut().zip(s.bytes()) {
+            ensure!(
+                c.is_ascii_digit(),
+                "expected passport ID to only contain digits, got {:?}",
+                s
+            );
+            *digit = c - b'0';
+        }
+        Ok(Self(digits))
+    }
+}
+
+impl Display for PassportId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self(digits) = self;
+        digits.iter().try_for_each(|digit| write!(f, "{}", digit))
+    }
+}
+
+#[test]
+fn passport_id_parsing() {
+    let passport_id = "000000001".parse::<PassportId>().unwrap();
+    assert_eq!(passport_id, PassportId([0, 0, 0, 0, 0, 0, 0, 0, 1]));
+    assert_eq!(passport_id.to_string(), "000000001");
+
+    assert!("0123456789".parse::<PassportId>().is_err());
+    assert!("01234567a".parse::<PassportId>().is_err());
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommonIdentityFields {
+    pub birth_year: u16,
+    pub issue_year: u16,
+    pub expiration_year: u16,
+    pub height: Height,
+    pub hair_color: String,
+    pub eye_color: EyeColor,
+    pub passport_id: PassportId,
+}
+
+impl TryFrom<RawCommonIdentityFields> for CommonIdentityFields {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawCommonIdentityFields) -> Result<Self, Self::Error> {
+        let RawCommonIdentityFields {
+            birth_year,
+            issue_year,
+            expiration_year,
+            height,
+            hair_color,
+            eye_color,
+            passport_id,
+        } = raw;
+
+        let parse_year = |field_name, year: String| {
+            year.parse::<u16>()
+                .with_context(|| anyhow!("failed to parse {} {:?}", field_name, year))
+        };
+
+        Ok(Self {
+            birth_year: parse_year("birth year", birth_year)?,
+            issue_year: parse_year("issue year", issue_year)?,
+            expiration_year: parse_year("expiration year", expiration_year)?,
+            height: height.parse()?,
+            hair_color,
+            eye_color: eye_color.parse()?,
+            passport_id: passport_id.parse()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Identity {
+    Passport {
+        country_id: String,
+        common: CommonIdentityFields,
+    },
+    NorthPoleCredentials(CommonIdentityFields),
+}
+
+impl TryFrom<RawIdentity> for Identity {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawIdentity) -> Result<Self, Self::Error> {
+        Ok(match raw {
+            RawIdentity::Passport { country_id, common } => Self::Passport {
+                country_id,
+                common: common.try_into()?,
+            },
+            RawIdentity::NorthPoleCredentials(common) => {
+                Self::NorthPoleCredentials(common.try_into()?)
+            }
+        })
+    }
+}
+
 fn count_records<F>(s: &str, mut f: F) -> anyhow::Result<usize>
 where
     F: FnMut(Map<String, JsonValue>) -> bool,
@@ -82,29 +288,24 @@ where
     })
 }
 
-fn part_1(s: &str) -> anyhow::Result<usize> {
+pub fn part_1(s: &str) -> anyhow::Result<usize> {
     count_records(s, |record| parse_identity_record(record).is_ok())
 }
 
 fn validate_birth_year(birth_year: &str) -> bool {
-    birth_year
-        .parse::<u16>()
-        .ok()
-        .filter(|&by| by >= 1920 && by <= 2002)
-        .is_some()
+    check_year(
+        birth_year,
+        1920..=2002,
+        IdentityFieldError::BirthYearMalformed,
+        IdentityFieldError::BirthYearOutOfRange,
+    )
+    .is_none()
 }
 
 fn validate_height(height: &str) -> bool {
     height
-        .strip_suffix("cm")
-        .and_then(|cm| cm.parse::<u8>().ok())
-        .filter(|&cm| cm >= 150 && cm <= 193)
-        .is_some()
-        || height
-            .strip_suffix("in")
-            .and_then(|ins| ins.parse::<u8>().ok())
-            .filter(|&ins| ins >= 59 && ins <= 76)
-            .is_some()
+        .parse::<Height>()
+        .map_or(false, |height| height.in_valid_range())
 }
 
 fn validate_hair_color(hair_color: &str) -> bool {
@@ -115,17 +316,55 @@ fn validate_hair_color(hair_color: &str) -> bool {
 }
 
 fn validate_eye_color(eye_color: &str) -> bool {
-    matches!(
-        &*eye_color,
-        "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth"
-    )
+    eye_color.parse::<EyeColor>().is_ok()
 }
 
 fn validate_passport_id(passport_id: &str) -> bool {
-    passport_id.len() == 9 && passport_id.chars().all(|c| c.is_ascii_digit())
+    passport_id.parse::<PassportId>().is_ok()
 }
 
-fn validate_common_identity_fields(common: &RawCommonIdentityFields) -> bool {
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IdentityFieldError {
+    BirthYearMalformed,
+    BirthYearOutOfRange,
+    IssueYearMalformed,
+    IssueYearOutOfRange,
+    ExpirationYearMalformed,
+    ExpirationYearOutOfRange,
+    HeightUnitUnknown,
+    HeightMalformed,
+    HeightOutOfRange,
+    HairColorMalformed,
+    EyeColorUnknown,
+    PassportIdMalformed,
+}
+
+fn check_year(
+    year: &str,
+    range: RangeInclusive<u16>,
+    malformed: IdentityFieldError,
+    out_of_range: IdentityFieldError,
+) -> Option<IdentityFieldError> {
+    match year.parse::<u16>() {
+        Err(_) => Some(malformed),
+        Ok(year) if !range.contains(&year) => Some(out_of_range),
+        Ok(_) => None,
+    }
+}
+
+fn check_height(height: &str) -> Option<IdentityFieldError> {
+    match height.parse::<Height>() {
+        Err(HeightParseError::UnknownUnit(_)) => Some(IdentityFieldError::HeightUnitUnknown),
+        Err(HeightParseError::Malformed { .. }) => Some(IdentityFieldError::HeightMalformed),
+        Ok(height) if !height.in_valid_range() => Some(IdentityFieldError::HeightOutOfRange),
+        Ok(_) => None,
+    }
+}
+
+/// Validates every field of `common`, collecting a failure for each field that is invalid.
+pub fn validate_common_identity_fields_detailed(
+    common: &RawCommonIdentityFields,
+) -> Result<(), Vec<IdentityFieldError>> {
     let RawCommonIdentityFields {
         birth_year,
         issue_year,
@@ -136,24 +375,48 @@ fn validate_common_identity_fields(common: &RawCommonIdentityFields) -> bool {
         passport_id,
     } = common;
 
-    validate_birth_year(&birth_year)
-        && issue_year
-            .parse::<u16>()
-            .ok()
-            .filter(|&iy| iy >= 2010 && iy <= 2020)
-            .is_some()
-        && expiration_year
-            .parse::<u16>()
-            .ok()
-            .filter(|&ey| ey >= 2020 && ey <= 2030)
-            .is_some()
-        && validate_height(&height)
-        && validate_hair_color(&hair_color)
-        && validate_eye_color(&eye_color)
-        && validate_passport_id(&passport_id)
-}
-
-fn part_2(s: &str) -> anyhow::Result<usize> {
+    let errors = [
+        check_year(
+            &birth_year,
+            1920..=2002,
+            IdentityFieldError::BirthYearMalformed,
+            IdentityFieldError::BirthYearOutOfRange,
+        ),
+        check_year(
+            &issue_year,
+            2010..=2020,
+            IdentityFieldError::IssueYearMalformed,
+            IdentityFieldError::IssueYearOutOfRange,
+        ),
+        check_year(
+            &expiration_year,
+            2020..=2030,
+            IdentityFieldError::ExpirationYearMalformed,
+            IdentityFieldError::ExpirationYearOutOfRange,
+        ),
+        check_height(&height),
+        Some(IdentityFieldError::HairColorMalformed).filter(|_| !validate_hair_color(&hair_color)),
+        Some(IdentityFieldError::EyeColorUnknown).filter(|_| !validate_eye_color(&eye_color)),
+        Some(IdentityFieldError::PassportIdMalformed)
+            .filter(|_| !validate_passport_id(&passport_id)),
+    ]
+    .iter()
+    .flatten()
+    .copied()
+    .collect::<Vec<_>>();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_common_identity_fields(common: &RawCommonIdentityFields) -> bool {
+    validate_common_identity_fields_detailed(common).is_ok()
+}
+
+pub fn part_2(s: &str) -> anyhow::Result<usize> {
     count_records(s, |record| {
         parse_identity_record(record).map_or(false, |identity| match identity {
             RawIdentity::NorthPoleCredentials(common)
@@ -170,6 +433,18 @@ fn p1_sample() {
     assert_eq!(part_1(SAMPLE).unwrap(), 2);
 }
 
+#[test]
+fn trailing_blank_lines() {
+    let with_trailing_blank_lines = format!("{}\n\n", SAMPLE);
+    assert!(with_trailing_blank_lines.ends_with("\n\n\n"));
+    assert_eq!(
+        parse_key_value_records(&with_trailing_blank_lines).count(),
+        parse_key_value_records(SAMPLE).count(),
+    );
+    assert_eq!(parse_key_value_records(SAMPLE).count(), 4);
+    assert_eq!(part_1(&with_trailing_blank_lines).unwrap(), 2);
+}
+
 #[test]
 fn p1_answer() {
     assert_eq!(part_1(INPUT).unwrap(), 239);
@@ -185,6 +460,31 @@ fn p2_sample() {
     assert!(!validate_height("190in"));
     assert!(!validate_height("190"));
 
+    let check_birth_year = |birth_year| {
+        check_year(
+            birth_year,
+            1920..=2002,
+            IdentityFieldError::BirthYearMalformed,
+            IdentityFieldError::BirthYearOutOfRange,
+        )
+    };
+    assert_eq!(check_birth_year("2002"), None);
+    assert_eq!(
+        check_birth_year("2003"),
+        Some(IdentityFieldError::BirthYearOutOfRange)
+    );
+
+    assert_eq!(check_height("60in"), None);
+    assert_eq!(check_height("190cm"), None);
+    assert_eq!(
+        check_height("190in"),
+        Some(IdentityFieldError::HeightOutOfRange)
+    );
+    assert_eq!(
+        check_height("190"),
+        Some(IdentityFieldError::HeightUnitUnknown)
+    );
+
     assert!(validate_hair_color("#123abc"));
     assert!(!validate_hair_color("#123abz"));
     assert!(!validate_hair_color("123abc"));
@@ -251,6 +551,124 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719
     }));
 }
 
+#[test]
+fn height_parsing() {
+    let height = "190cm".parse::<Height>().unwrap();
+    assert_eq!(height, Height::Cm(190));
+    assert!(height.in_valid_range());
+
+    let height = "190in".parse::<Height>().unwrap();
+    assert_eq!(height, Height::In(190));
+    assert!(!height.in_valid_range());
+
+    assert!("190".parse::<Height>().is_err());
+    assert!("190ft".parse::<Height>().is_err());
+}
+
+#[test]
+fn detailed_validation() {
+    let (record,) = parse_identities(
+        "hcl:#123abc ecl:wat hgt:190in pid:000000001 iyr:2015 eyr:2025 byr:1990\n",
+    )
+    .collect_tuple()
+    .unwrap();
+    let common = match record.unwrap() {
+        RawIdentity::NorthPoleCredentials(common)
+        | RawIdentity::Passport {
+            common,
+            country_id: _,
+        } => common,
+    };
+    assert_eq!(
+        validate_common_identity_fields_detailed(&common),
+        Err(vec![
+            IdentityFieldError::HeightOutOfRange,
+            IdentityFieldError::EyeColorUnknown,
+        ]),
+    );
+    assert!(!validate_common_identity_fields(&common));
+
+    assert_eq!(
+        check_height("190"),
+        Some(IdentityFieldError::HeightUnitUnknown)
+    );
+    assert_eq!(
+        check_height("xcm"),
+        Some(IdentityFieldError::HeightMalformed)
+    );
+    assert_eq!(check_height("190cm"), None);
+}
+
+#[test]
+fn typed_identities() {
+    let identities = parse_identities(
+        "\
+pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+hcl:#888785
+hgt:164cm byr:2001 iyr:2015 cid:88
+pid:545766238 ecl:hzl
+eyr:2022
+
+iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719
+",
+    )
+    .map(|res| Identity::try_from(res.unwrap()).unwrap())
+    .collect::<Vec<_>>();
+
+    assert_eq!(
+        identities,
+        [
+            Identity::NorthPoleCredentials(CommonIdentityFields {
+                birth_year: 1980,
+                issue_year: 2012,
+                expiration_year: 2030,
+                height: Height::In(74),
+                hair_color: "#623a2f".to_owned(),
+                eye_color: EyeColor::Grn,
+                passport_id: "087499704".parse().unwrap(),
+            }),
+            Identity::Passport {
+                country_id: "129".to_owned(),
+                common: CommonIdentityFields {
+                    birth_year: 1989,
+                    issue_year: 2014,
+                    expiration_year: 2029,
+                    height: Height::Cm(165),
+                    hair_color: "#a97842".to_owned(),
+                    eye_color: EyeColor::Blu,
+                    passport_id: "896056539".parse().unwrap(),
+                },
+            },
+            Identity::Passport {
+                country_id: "88".to_owned(),
+                common: CommonIdentityFields {
+                    birth_year: 2001,
+                    issue_year: 2015,
+                    expiration_year: 2022,
+                    height: Height::Cm(164),
+                    hair_color: "#888785".to_owned(),
+                    eye_color: EyeColor::Hzl,
+                    passport_id: "545766238".parse().unwrap(),
+                },
+            },
+            Identity::NorthPoleCredentials(CommonIdentityFields {
+                birth_year: 1944,
+                issue_year: 2010,
+                expiration_year: 2021,
+                height: Height::Cm(158),
+                hair_color: "#b6652a".to_owned(),
+                eye_color: EyeColor::Blu,
+                passport_id: "093154719".parse().unwrap(),
+            }),
+        ],
+    );
+}
+
 #[test]
 fn p2_answer() {
     assert_eq!(part_2(INPUT).unwrap(), 188);
@@ -1,7 +1,12 @@
 use {
     crate::parsing::lines_without_endings,
-    anyhow::{bail, ensure, Context},
-    std::{ops::Sub, str::FromStr},
+    anyhow::{anyhow, Context},
+    std::{
+        convert::TryFrom,
+        fmt::{self, Display, Formatter},
+        ops::Sub,
+        str::FromStr,
+    },
     ux::{i11, u10, u3, u7},
 };
 
@@ -14,49 +19,107 @@ fn p1_sample() {
         let seat_id = s.parse().unwrap();
         assert_eq!(SeatId(u10::new(expected_seat_id)), seat_id);
         assert_eq!(seat_id.row_and_seat(), (u7::new(row), u3::new(seat)));
+        assert_eq!(SeatId::from(seat_id.row_and_seat()), seat_id);
     }
     test_seat_id("FBFBBFFRLR", (44, 5), 357);
     test_seat_id("BFFFBBFRRR", (70, 7), 567);
     test_seat_id("FFFBBBFRRR", (14, 7), 119);
     test_seat_id("BBFFBBFRLL", (102, 4), 820);
+
+    assert_eq!(decode_seat(b"FBFBBFFRLR"), Ok(SeatId(u10::new(357))));
+    assert_eq!(decode_seat(b"BFFFBBFRRR"), Ok(SeatId(u10::new(567))));
+    assert_eq!(decode_seat(b"FFFBBBFRRR"), Ok(SeatId(u10::new(119))));
+    assert_eq!(decode_seat(b"BBFFBBFRLL"), Ok(SeatId(u10::new(820))));
+}
+
+#[test]
+fn display_round_trip() {
+    assert_eq!(SeatId(u10::new(357)).to_string(), "FBFBBFFRLR");
+    all_seat_ids().for_each(|s| assert_eq!(s.to_string().parse::<SeatId>().unwrap(), s));
+}
+
+fn parse_seat_ids(s: &str) -> anyhow::Result<Vec<SeatId>> {
+    lines_without_endings(s)
+        .zip(1..)
+        .map(|(l, line_num)| {
+            l.parse::<SeatId>()
+                .with_context(|| anyhow!("failed to parse line {}", line_num))
+        })
+        .collect()
+}
+
+/// Finds the highest seat ID among the boarding passes in `s`.
+pub fn part_1(s: &str) -> anyhow::Result<SeatId> {
+    parse_seat_ids(s)?
+        .into_iter()
+        .max()
+        .context("no boarding passes specified")
 }
 
 #[test]
 fn p1_answer() {
-    assert_eq!(
-        lines_without_endings(INPUT)
-            .map(|l| l.parse::<SeatId>().unwrap())
-            .max()
-            .unwrap(),
-        SeatId(u10::new(806))
-    );
+    assert_eq!(part_1(INPUT).unwrap(), SeatId(u10::new(806)));
+}
+
+/// Finds the only seat missing from the boarding passes in `s` whose neighbors are both present.
+pub fn part_2(s: &str) -> anyhow::Result<SeatId> {
+    find_missing_seat(&parse_seat_ids(s)?).context("did not find a lonely empty space")
 }
 
 #[test]
 fn p2_answer() {
-    let mut seats = lines_without_endings(INPUT)
-        .map(|l| l.parse::<SeatId>())
-        .collect::<Result<Vec<_>, _>>()
-        .unwrap();
-
-    seats.sort();
-
-    let available_seat = seats[..]
-        .windows(2)
-        .find_map(|window| match *window {
-            [before, after] => {
-                if after - before == i11::new(2) {
-                    Some(before.checked_add(1).unwrap())
-                } else {
-                    None
-                }
-            }
-            _ => unreachable!(),
+    assert_eq!(part_2(INPUT).unwrap(), SeatId(u10::new(562)));
+}
+
+/// Yields every representable seat ID, in ascending order.
+pub fn all_seat_ids() -> impl Iterator<Item = SeatId> {
+    (0..=SeatId::TEN_RIGHTMOST_BITS).map(|id| SeatId(u10::new(id)))
+}
+
+/// Finds the first seat ID absent from `present` whose neighboring IDs are both present.
+pub fn find_missing_seat(present: &[SeatId]) -> Option<SeatId> {
+    occupancy_report(present)?.missing.first().copied()
+}
+
+/// The range of seat IDs spanned by a set of boarding passes, and the seats missing within it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OccupancyReport {
+    pub min: SeatId,
+    pub max: SeatId,
+    /// Seat IDs absent between `min` and `max` whose neighboring IDs are both present, in
+    /// ascending order.
+    pub missing: Vec<SeatId>,
+}
+
+/// Summarizes the seat IDs in `ids`, or returns `None` if there are none.
+pub fn occupancy_report(ids: &[SeatId]) -> Option<OccupancyReport> {
+    let min = ids.iter().copied().min()?;
+    let max = ids.iter().copied().max()?;
+
+    let mut is_present = [false; SeatId::TEN_RIGHTMOST_BITS as usize + 1];
+    ids.iter()
+        .for_each(|&SeatId(id)| is_present[usize::from(u16::from(id))] = true);
+
+    let missing = all_seat_ids()
+        .skip(1)
+        .zip(is_present.windows(3))
+        .filter_map(|(id, window)| match *window {
+            [true, false, true] => Some(id),
+            _ => None,
         })
-        .context("did not find a lonely empty space")
-        .unwrap();
+        .collect();
 
-    assert_eq!(available_seat, SeatId(u10::new(562)));
+    Some(OccupancyReport { min, max, missing })
+}
+
+#[test]
+fn input_occupancy_report() {
+    let report = occupancy_report(&parse_seat_ids(INPUT).unwrap()).unwrap();
+    assert_eq!(report.max, SeatId(u10::new(806)));
+    assert_eq!(report.missing, [SeatId(u10::new(562))]);
+    assert!(report.min < report.missing[0]);
+
+    assert_eq!(occupancy_report(&[]), None);
 }
 
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
@@ -84,38 +147,118 @@ impl SeatId {
     }
 }
 
+/// A failure to decode a boarding pass into a [`SeatId`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SeatParseError {
+    WrongLength,
+    /// The character at `index` (among the first 7) was not `F` or `B`.
+    BadRowChar {
+        index: usize,
+        found: u8,
+    },
+    /// The character at `index` (among the last 3) was not `L` or `R`.
+    BadColChar {
+        index: usize,
+        found: u8,
+    },
+}
+
+impl Display for SeatParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::WrongLength => write!(f, "expected 10 bytes of input"),
+            Self::BadRowChar { index, found } => write!(
+                f,
+                "expected 'F' or 'B' for character {}, but got {:?}",
+                index,
+                char::from(found),
+            ),
+            Self::BadColChar { index, found } => write!(
+                f,
+                "expected 'L' or 'R' for character {}, but got {:?}",
+                index,
+                char::from(found),
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SeatParseError {}
+
+/// Decodes a boarding pass, where each of the 10 bytes is a bit of the seat ID, most significant
+/// first.
+pub fn decode_seat(bytes: &[u8; 10]) -> Result<SeatId, SeatParseError> {
+    const TENTH_BIT_SET: u16 = 0b10_0000_0000;
+
+    let mut seat = 0;
+    for (index, &found) in bytes.iter().enumerate() {
+        seat |= match (index < 7, found) {
+            (true, b'F') | (false, b'L') => 0,
+            (true, b'B') | (false, b'R') => TENTH_BIT_SET >> index,
+            (true, _) => return Err(SeatParseError::BadRowChar { index, found }),
+            (false, _) => return Err(SeatParseError::BadColChar { index, found }),
+        };
+    }
+    Ok(SeatId(u10::new(seat)))
+}
+
+#[test]
+fn decode_seat_errors() {
+    assert_eq!(
+        "FBFBBFFRL".parse::<SeatId>(),
+        Err(SeatParseError::WrongLength)
+    );
+    assert_eq!(
+        "FBFBBFFRLRR".parse::<SeatId>(),
+        Err(SeatParseError::WrongLength)
+    );
+    assert_eq!(
+        decode_seat(b"FBFBLFFRLR"),
+        Err(SeatParseError::BadRowChar {
+            index: 4,
+            found: b'L'
+        }),
+    );
+    assert_eq!(
+        decode_seat(b"FBFBBFFRBR"),
+        Err(SeatParseError::BadColChar {
+            index: 8,
+            found: b'B'
+        }),
+    );
+}
+
 impl FromStr for SeatId {
-    type Err = anyhow::Error;
+    type Err = SeatParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        ensure!(s.len() == 10, "expected 10 bytes of input, got {}", s.len(),);
-
-        let mut seat = 0;
-        const TENTH_BIT_SET: u16 = 0b10_0000_0000;
-        let mut char_indices = s.chars().enumerate();
-        for (count, c) in char_indices.by_ref().take(7) {
-            seat |= match c {
-                'F' => 0,
-                'B' => TENTH_BIT_SET >> count,
-                _ => bail!(
-                    "expected 'F' or 'B' for character {}, but got {:?}",
-                    count,
-                    c,
-                ),
-            };
-        }
-        for (count, c) in char_indices.take(3) {
-            seat |= match c {
-                'L' => 0,
-                'R' => TENTH_BIT_SET >> count,
-                _ => bail!(
-                    "expected 'L' or 'R' for character {}, but got {:?}",
-                    count,
-                    c,
-                ),
+        let bytes = <&[u8; 10]>::try_from(s.as_bytes()).map_err(|_| SeatParseError::WrongLength)?;
+        decode_seat(bytes)
+    }
+}
+
+impl Display for SeatId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self(s) = *self;
+        let s = u16::from(s);
+        (0..10).rev().try_for_each(|bit_idx| {
+            let is_set = s & (1 << bit_idx) != 0;
+            let c = match (bit_idx >= 3, is_set) {
+                (true, false) => 'F',
+                (true, true) => 'B',
+                (false, false) => 'L',
+                (false, true) => 'R',
             };
-        }
-        Ok(Self(u10::new(seat)))
+            write!(f, "{}", c)
+        })
+    }
+}
+
+impl From<(u7, u3)> for SeatId {
+    fn from((row, seat): (u7, u3)) -> Self {
+        Self(u10::new(
+            (u16::from(u8::from(row)) << 3) | u16::from(u8::from(seat)),
+        ))
     }
 }
 
@@ -1,4 +1,7 @@
-use {crate::parsing::lines_without_endings, std::collections::HashSet};
+use {
+    crate::parsing::{blank_line_separated_groups, lines_without_endings},
+    itertools::Itertools,
+};
 
 const SAMPLE: &str = "\
 abc
@@ -23,16 +26,27 @@ fn p1_sample() {
     assert_eq!(sum_of_unique_question_answer_counts(SAMPLE), 11);
 }
 
-fn sum_of_unique_question_answer_counts(s: &str) -> usize {
-    s.split("\n\n")
-        .map(|group| {
-            group
-                .split_whitespace()
-                .flat_map(|c| c.chars())
-                .collect::<HashSet<_>>()
-                .len()
-        })
-        .sum()
+/// Maps each question answered with `a` through `z` in `s` to a bit, with `a` as the least
+/// significant bit. Any other characters (e.g., whitespace) are ignored.
+fn answers_to_mask(s: &str) -> u32 {
+    s.bytes()
+        .filter(u8::is_ascii_lowercase)
+        .fold(0, |mask, question| mask | 1 << (question - b'a'))
+}
+
+#[test]
+fn answers_to_mask_ignores_non_questions() {
+    assert_eq!(answers_to_mask("abc"), 0b111);
+    assert_eq!(answers_to_mask("z"), 1 << 25);
+    assert_eq!(answers_to_mask("a\nB c?"), 0b101);
+}
+
+pub fn unique_answer_counts(s: &str) -> impl Iterator<Item = usize> + '_ {
+    blank_line_separated_groups(s).map(|group| answers_to_mask(group).count_ones() as usize)
+}
+
+pub fn sum_of_unique_question_answer_counts(s: &str) -> usize {
+    unique_answer_counts(s).sum()
 }
 
 const INPUT: &str = include_str!("d06.txt");
@@ -50,23 +64,30 @@ fn p2_sample() {
     );
 }
 
-fn sum_of_group_individuals_who_answered_yes_in_each_group(s: &str) -> usize {
-    s.split("\n\n")
-        .map(|group| {
-            let mut questions = lines_without_endings(group);
-            let mut individuals_responding_yes_to_everything =
-                questions.next().unwrap().chars().collect::<HashSet<_>>();
-            questions.for_each(|question| {
-                individuals_responding_yes_to_everything = question
-                    .chars()
-                    .filter(|individual_responding_yes| {
-                        individuals_responding_yes_to_everything.contains(individual_responding_yes)
-                    })
-                    .collect::<HashSet<_>>();
-            });
-            individuals_responding_yes_to_everything.len()
-        })
-        .sum()
+pub fn unanimous_answer_counts(s: &str) -> impl Iterator<Item = usize> + '_ {
+    blank_line_separated_groups(s).map(|group| {
+        lines_without_endings(group)
+            .map(answers_to_mask)
+            .fold1(|answered_by_all, individual| answered_by_all & individual)
+            .unwrap_or(0)
+            .count_ones() as usize
+    })
+}
+
+pub fn sum_of_group_individuals_who_answered_yes_in_each_group(s: &str) -> usize {
+    unanimous_answer_counts(s).sum()
+}
+
+#[test]
+fn per_group_counts() {
+    assert_eq!(
+        unique_answer_counts(SAMPLE).collect::<Vec<_>>(),
+        [3, 3, 3, 1, 1],
+    );
+    assert_eq!(
+        unanimous_answer_counts(SAMPLE).collect::<Vec<_>>(),
+        [3, 0, 1, 1, 1],
+    );
 }
 
 #[test]
@@ -4,6 +4,7 @@ use {
     itertools::Itertools,
     std::{
         collections::{hash_map::HashMap, HashSet},
+        iter::once,
         num::NonZeroU8,
         ops::Deref,
     },
@@ -29,7 +30,7 @@ fn p1_sample() {
 }
 
 #[derive(Debug)]
-struct LuggageRules<'a>(HashMap<&'a str, LuggageRule<'a>>);
+pub struct LuggageRules<'a>(HashMap<&'a str, LuggageRule<'a>>);
 
 impl<'a> Deref for LuggageRules<'a> {
     type Target = HashMap<&'a str, LuggageRule<'a>>;
@@ -40,7 +41,7 @@ impl<'a> Deref for LuggageRules<'a> {
 }
 
 #[derive(Debug)]
-struct LuggageRule<'a>(HashMap<&'a str, NonZeroU8>);
+pub struct LuggageRule<'a>(HashMap<&'a str, NonZeroU8>);
 
 impl<'a> Deref for LuggageRule<'a> {
     type Target = HashMap<&'a str, NonZeroU8>;
@@ -50,7 +51,7 @@ impl<'a> Deref for LuggageRule<'a> {
     }
 }
 
-fn parse_luggage_rules(s: &str) -> anyhow::Result<LuggageRules<'_>> {
+pub fn parse_luggage_rules(s: &str) -> anyhow::Result<LuggageRules<'_>> {
     let mut rules = HashMap::new();
     let mut rules_lines = HashMap::<_, u64>::new();
     let mut unverified = HashSet::new();
@@ -112,7 +113,7 @@ fn parse_luggage_rules(s: &str) -> anyhow::Result<LuggageRules<'_>> {
                                 })?
                             };
 
-                            if rules.get(contained_color).is_none() {
+                            if contained_color != color && rules.get(contained_color).is_none() {
                                 unverified.insert(contained_color);
                             }
 
@@ -132,38 +133,116 @@ fn parse_luggage_rules(s: &str) -> anyhow::Result<LuggageRules<'_>> {
         but are unspecified: {:?}",
         unverified,
     );
+    ensure_no_cycles(&rules)?;
     Ok(LuggageRules(rules))
 }
 
-fn part_1(s: &str) -> anyhow::Result<usize> {
-    fn does_color_contain_color<'a>(
-        memo: &mut HashMap<&'a str, bool>,
-        luggage_rules: &LuggageRules<'a>,
-        container: &'a str,
-        containee: &'a str,
-    ) -> bool {
-        if let Some(&memoized) = memo.get(container) {
-            return memoized;
+/// Ensures that no color transitively contains itself, naming the colors of the first cycle
+/// found otherwise.
+fn ensure_no_cycles(rules: &HashMap<&str, LuggageRule<'_>>) -> anyhow::Result<()> {
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    enum VisitState {
+        InProgress,
+        Done,
+    }
+
+    let mut visit_states = HashMap::new();
+    for &start in rules.keys() {
+        if visit_states.contains_key(start) {
+            continue;
+        }
+        visit_states.insert(start, VisitState::InProgress);
+        let mut path = vec![(start, rules[start].keys())];
+        while let Some((_color, contained_colors)) = path.last_mut() {
+            match contained_colors.next().copied() {
+                Some(contained) => match visit_states.get(contained) {
+                    Some(VisitState::InProgress) => {
+                        let cycle_start = path
+                            .iter()
+                            .position(|&(color, _)| color == contained)
+                            .unwrap();
+                        bail!(
+                            "luggage rules contain a cycle: {}",
+                            path[cycle_start..]
+                                .iter()
+                                .map(|&(color, _)| color)
+                                .chain(once(contained))
+                                .map(|color| format!("{:?}", color))
+                                .join(" -> "),
+                        );
+                    }
+                    Some(VisitState::Done) => (),
+                    None => {
+                        visit_states.insert(contained, VisitState::InProgress);
+                        path.push((contained, rules[contained].keys()));
+                    }
+                },
+                None => {
+                    let (color, _) = path.pop().unwrap();
+                    visit_states.insert(color, VisitState::Done);
+                }
+            }
         }
-        let answer = luggage_rules
-            .get(container)
-            .unwrap()
-            .keys()
-            .any(|&contained| {
-                contained == containee
-                    || does_color_contain_color(memo, luggage_rules, contained, containee)
-            });
-        memo.insert(container, answer);
-        answer
     }
-    let luggage_rules = parse_luggage_rules(s)?;
-    let mut memoized_query = HashMap::new();
-    Ok(luggage_rules
-        .keys()
-        .filter(|color| {
-            does_color_contain_color(&mut memoized_query, &luggage_rules, color, "shiny gold")
+    Ok(())
+}
+
+/// Builds an index from each contained color to the set of colors that directly contain it.
+fn reverse_luggage_rules<'a>(rules: &LuggageRules<'a>) -> HashMap<&'a str, HashSet<&'a str>> {
+    let mut containers_by_containee = HashMap::<_, HashSet<_>>::new();
+    rules.iter().for_each(|(&container, rule)| {
+        rule.keys().for_each(|&contained| {
+            containers_by_containee
+                .entry(contained)
+                .or_default()
+                .insert(container);
         })
-        .count())
+    });
+    containers_by_containee
+}
+
+/// Finds every color that can transitively contain `target`.
+pub fn colors_that_can_contain<'a>(rules: &LuggageRules<'a>, target: &str) -> HashSet<&'a str> {
+    let containers_by_containee = reverse_luggage_rules(rules);
+    let mut found = HashSet::new();
+    let mut to_visit = vec![target];
+    while let Some(containee) = to_visit.pop() {
+        if let Some(containers) = containers_by_containee.get(containee) {
+            containers.iter().copied().for_each(|container| {
+                if found.insert(container) {
+                    to_visit.push(container);
+                }
+            });
+        }
+    }
+    found
+}
+
+pub fn part_1(s: &str) -> anyhow::Result<usize> {
+    Ok(colors_that_can_contain(&parse_luggage_rules(s)?, "shiny gold").len())
+}
+
+#[test]
+fn reverse_index() {
+    let rules = parse_luggage_rules(SAMPLE).unwrap();
+
+    let containers_by_containee = reverse_luggage_rules(&rules);
+    assert_eq!(
+        containers_by_containee["shiny gold"],
+        ["bright white", "muted yellow"].iter().copied().collect(),
+    );
+    assert_eq!(
+        containers_by_containee["bright white"],
+        ["light red", "dark orange"].iter().copied().collect(),
+    );
+
+    assert_eq!(
+        colors_that_can_contain(&rules, "shiny gold"),
+        ["bright white", "muted yellow", "light red", "dark orange"]
+            .iter()
+            .copied()
+            .collect(),
+    );
 }
 
 #[test]
@@ -171,6 +250,31 @@ fn p1_answer() {
     assert_eq!(part_1(INPUT).unwrap(), 151);
 }
 
+#[test]
+fn cyclic_rules() {
+    let err = parse_luggage_rules(
+        "\
+shiny gold bags contain 1 dark red bag.
+dark red bags contain 1 shiny gold bag.
+",
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err == r#"luggage rules contain a cycle: "shiny gold" -> "dark red" -> "shiny gold""#
+            || err == r#"luggage rules contain a cycle: "dark red" -> "shiny gold" -> "dark red""#,
+        "unexpected error: {}",
+        err,
+    );
+
+    assert_eq!(
+        parse_luggage_rules("shiny gold bags contain 2 shiny gold bags.\n")
+            .unwrap_err()
+            .to_string(),
+        r#"luggage rules contain a cycle: "shiny gold" -> "shiny gold""#,
+    );
+}
+
 #[test]
 fn p2_sample_1() {
     assert_eq!(part_2(SAMPLE).unwrap(), 32)
@@ -195,33 +299,96 @@ dark violet bags contain no other bags.
     );
 }
 
-fn part_2(s: &str) -> anyhow::Result<u32> {
+pub fn part_2(s: &str) -> anyhow::Result<u32> {
     fn num_bags_for_color<'a>(
         memo: &mut HashMap<&'a str, u32>,
         luggage_rules: &LuggageRules<'a>,
         container: &'a str,
-    ) -> u32 {
-        if let Some(&memoized) = memo.get(container) {
-            return memoized;
+    ) -> anyhow::Result<u32> {
+        // NOTE: This is a post-order traversal with an explicit stack, rather than recursion, so
+        // that deeply nested rules can't overflow the call stack.
+        let mut to_visit: Vec<&'a str> = vec![container];
+        while let Some(&color) = to_visit.last() {
+            if memo.contains_key(color) {
+                to_visit.pop();
+                continue;
+            }
+            let rule = luggage_rules
+                .get(color)
+                .with_context(|| anyhow!("no rule for {:?}", color))?;
+            let num_to_visit = to_visit.len();
+            to_visit.extend(
+                rule.keys()
+                    .copied()
+                    .filter(|contained| !memo.contains_key(contained)),
+            );
+            if to_visit.len() == num_to_visit {
+                let answer = rule
+                    .iter()
+                    .try_fold(1u32, |sum, (&contained, count)| {
+                        memo[contained]
+                            .checked_mul(count.get().into())
+                            .and_then(|count| sum.checked_add(count))
+                    })
+                    .with_context(|| anyhow!("number of bags in {:?} overflowed", color))?;
+                memo.insert(color, answer);
+                to_visit.pop();
+            }
         }
-        let answer = luggage_rules
-            .get(container)
-            .unwrap()
-            .iter()
-            .map(|(&contained, count)| {
-                num_bags_for_color(memo, luggage_rules, contained)
-                    .checked_mul(count.get().into())
-                    .unwrap()
-            })
-            .fold(1u32, |sum, count| sum.checked_add(count).unwrap());
-        memo.insert(container, answer);
-        answer
+        Ok(memo[container])
     }
     Ok(
-        num_bags_for_color(&mut HashMap::new(), &parse_luggage_rules(s)?, "shiny gold") - 1, /* because we don't include the outermost bag (???) */
+        num_bags_for_color(&mut HashMap::new(), &parse_luggage_rules(s)?, "shiny gold")? - 1, /* because we don't include the outermost bag (???) */
     )
 }
 
+#[test]
+fn p2_deep_chain() {
+    const CHAIN_LEN: usize = 5000;
+    let color = |idx| {
+        if idx == 0 {
+            "shiny gold".to_owned()
+        } else {
+            format!("dark {}", idx)
+        }
+    };
+    let rules = (0..CHAIN_LEN)
+        .map(|idx| {
+            if idx + 1 == CHAIN_LEN {
+                format!("{} bags contain no other bags.\n", color(idx))
+            } else {
+                format!("{} bags contain 1 {} bag.\n", color(idx), color(idx + 1))
+            }
+        })
+        .collect::<String>();
+    assert_eq!(part_2(&rules).unwrap(), CHAIN_LEN as u32 - 1);
+}
+
+#[test]
+fn p2_malformed_rules() {
+    assert_eq!(
+        part_2("light red bags contain no other bags.\n")
+            .unwrap_err()
+            .to_string(),
+        "no rule for \"shiny gold\"",
+    );
+    assert_eq!(
+        part_2(
+            "\
+shiny gold bags contain 255 dark red bags.
+dark red bags contain 255 dark orange bags.
+dark orange bags contain 255 dark yellow bags.
+dark yellow bags contain 255 dark green bags.
+dark green bags contain 255 dark blue bags.
+dark blue bags contain no other bags.
+"
+        )
+        .unwrap_err()
+        .to_string(),
+        "number of bags in \"shiny gold\" overflowed",
+    );
+}
+
 #[test]
 fn p2_answer() {
     assert_eq!(part_2(INPUT).unwrap(), 41559);
@@ -2,7 +2,12 @@ use {
     crate::parsing::lines_without_endings,
     anyhow::{anyhow, bail, Context},
     itertools::Itertools,
-    std::{collections::HashSet, convert::TryInto},
+    std::{
+        collections::HashSet,
+        convert::{TryFrom, TryInto},
+        error::Error as StdError,
+        fmt::{self, Display, Formatter},
+    },
 };
 
 const SAMPLE: &str = "\
@@ -24,91 +29,320 @@ fn p1_sample() {
     assert_eq!(part_1(SAMPLE).unwrap(), 5);
 }
 
-#[derive(Clone, Debug)]
-struct BootCodeInstruction {
-    operation: BootCodeOperation,
-    argument: i16,
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BootCodeInstruction {
+    pub operation: BootCodeOperation,
+    pub argument: i16,
 }
 
-#[derive(Clone, Copy, Debug)]
-enum BootCodeOperation {
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BootCodeOperation {
     Accumulate,
     Jump,
     NoOp,
+    /// Appends the current accumulator value to the emulator's output trace.
+    Output,
+    /// Stops execution cleanly.
+    Halt,
 }
 
-#[derive(Debug)]
-struct BootCodeEmulator {
+impl BootCodeOperation {
+    const ACCUMULATE: &'static str = "acc";
+    const JUMP: &'static str = "jmp";
+    const NO_OP: &'static str = "nop";
+    const OUTPUT: &'static str = "out";
+    const HALT: &'static str = "hlt";
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Accumulate => Self::ACCUMULATE,
+            Self::Jump => Self::JUMP,
+            Self::NoOp => Self::NO_OP,
+            Self::Output => Self::OUTPUT,
+            Self::Halt => Self::HALT,
+        }
+    }
+}
+
+impl Display for BootCodeOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())
+    }
+}
+
+impl Display for BootCodeInstruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            operation,
+            argument,
+        } = self;
+        write!(f, "{} {:+}", operation, argument)
+    }
+}
+
+/// Renders `program` with one instruction per line, each prefixed by its index.
+pub fn disassemble(program: &[BootCodeInstruction]) -> String {
+    let idx_width = program.len().saturating_sub(1).to_string().len();
+    program
+        .iter()
+        .enumerate()
+        .map(|(idx, instruction)| format!("{:>width$}: {}\n", idx, instruction, width = idx_width))
+        .collect()
+}
+
+#[test]
+fn disassembly() {
+    let program = parse_instructions(SAMPLE).unwrap();
+    let displayed = program
+        .iter()
+        .map(|instruction| format!("{}\n", instruction))
+        .collect::<String>();
+    assert_eq!(displayed, SAMPLE);
+    assert_eq!(parse_instructions(&displayed).unwrap(), program);
+
+    assert_eq!(
+        disassemble(&program),
+        "\
+0: nop +0
+1: acc +1
+2: jmp +4
+3: acc +3
+4: jmp -3
+5: acc -99
+6: acc +1
+7: jmp -4
+8: acc +6
+",
+    );
+}
+
+/// Marks which instructions in `program` can be executed when starting from the first one, where
+/// `jmp` continues at its target, `hlt` goes nowhere, and everything else falls through to the
+/// next instruction. Each instruction is visited at most once, so loops are fine.
+pub fn reachable_instructions(program: &[BootCodeInstruction]) -> Vec<bool> {
+    let mut reachable = vec![false; program.len()];
+    let mut to_visit = vec![0];
+    while let Some(idx) = to_visit.pop() {
+        match reachable.get_mut(idx) {
+            Some(visited @ false) => *visited = true,
+            _ => continue,
+        }
+        let BootCodeInstruction {
+            operation,
+            argument,
+        } = program[idx];
+        let next = match operation {
+            BootCodeOperation::Jump => (idx as isize)
+                .checked_add(argument.into())
+                .and_then(|next| next.try_into().ok()),
+            BootCodeOperation::Halt => None,
+            BootCodeOperation::Accumulate | BootCodeOperation::NoOp | BootCodeOperation::Output => {
+                idx.checked_add(1)
+            }
+        };
+        to_visit.extend(next);
+    }
+    reachable
+}
+
+#[test]
+fn sample_reachable_instructions() {
+    let reachable = reachable_instructions(&parse_instructions(SAMPLE).unwrap());
+    assert_eq!(
+        reachable,
+        [true, true, true, true, true, false, true, true, false],
+    );
+
+    assert!(reachable_instructions(&[]).is_empty());
+    assert_eq!(
+        reachable_instructions(&parse_instructions("hlt +0\nnop +0\n").unwrap()),
+        [true, false],
+    );
+}
+
+#[derive(Debug, Default)]
+pub struct BootCodeEmulator {
     instruction_counter: usize,
     accumulator: i32,
+    output: Vec<i32>,
+    halted: bool,
 }
 
 impl BootCodeEmulator {
-    fn zeroed() -> Self {
-        Self {
-            instruction_counter: 0,
-            accumulator: 0,
-        }
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn execute_single_instruction(
-        &mut self,
-        instructions: &[BootCodeInstruction],
-    ) -> anyhow::Result<()> {
-        (|| {
-            let Self {
-                instruction_counter,
-                accumulator,
-            } = self;
-
-            let instruction = instructions
-                .get(*instruction_counter)
-                .context("instruction counter out-of-bounds")?;
-            let increment_inst_counter = |counter: &mut usize| {
-                counter
-                    .checked_add(1)
-                    .map(|new_counter| *counter = new_counter)
-                    .context("next instruction counter increment overflows")
-            };
-            match instruction.clone() {
-                BootCodeInstruction {
-                    operation: BootCodeOperation::NoOp,
-                    argument: _,
-                } => increment_inst_counter(instruction_counter),
-                BootCodeInstruction {
-                    operation: BootCodeOperation::Jump,
-                    argument,
-                } => if argument.is_positive() {
-                    instruction_counter
-                        .checked_add(argument.try_into().unwrap())
-                        .context("jump instruction overflowed")
-                } else {
-                    instruction_counter
-                        .checked_sub(argument.checked_neg().unwrap().try_into().unwrap())
-                        .context("jump instruction underflowed")
-                }
-                .map(|new_counter| *instruction_counter = new_counter),
-                BootCodeInstruction {
-                    operation: BootCodeOperation::Accumulate,
-                    argument,
-                } => accumulator
-                    .checked_add(argument.into())
-                    .context("accumulator went out-of-range")
-                    .map(|new_acc| *accumulator = new_acc)
-                    .and_then(|()| increment_inst_counter(instruction_counter)),
+    pub fn accumulator(&self) -> i32 {
+        self.accumulator
+    }
+
+    pub fn instruction_counter(&self) -> usize {
+        self.instruction_counter
+    }
+
+    /// Accumulator values emitted by `out` instructions so far, in execution order.
+    pub fn output(&self) -> &[i32] {
+        &self.output
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Executes the instruction in `instructions` at the current instruction counter.
+    pub fn step(&mut self, instructions: &[BootCodeInstruction]) -> Result<(), BootCodeError> {
+        let Self {
+            instruction_counter,
+            accumulator,
+            output,
+            halted,
+        } = self;
+
+        if *halted {
+            return Err(BootCodeError::AlreadyHalted);
+        }
+
+        let at = *instruction_counter;
+        let instruction = instructions
+            .get(at)
+            .ok_or(BootCodeError::CounterOutOfBounds {
+                counter: at,
+                len: instructions.len(),
+            })?;
+        let increment_inst_counter = |counter: &mut usize| {
+            counter
+                .checked_add(1)
+                .map(|new_counter| *counter = new_counter)
+                .ok_or(BootCodeError::CounterOverflow { at })
+        };
+        match instruction.clone() {
+            BootCodeInstruction {
+                operation: BootCodeOperation::NoOp,
+                argument: _,
+            } => increment_inst_counter(instruction_counter),
+            BootCodeInstruction {
+                operation: BootCodeOperation::Jump,
+                argument,
+            } => isize::try_from(*instruction_counter)
+                .ok()
+                .and_then(|counter| counter.checked_add(argument.into()))
+                .and_then(|new_counter| usize::try_from(new_counter).ok())
+                .map(|new_counter| *instruction_counter = new_counter)
+                .ok_or(BootCodeError::CounterOverflow { at }),
+            BootCodeInstruction {
+                operation: BootCodeOperation::Accumulate,
+                argument,
+            } => accumulator
+                .checked_add(argument.into())
+                .map(|new_acc| *accumulator = new_acc)
+                .ok_or(BootCodeError::AccumulatorOverflow { at })
+                .and_then(|()| increment_inst_counter(instruction_counter)),
+            BootCodeInstruction {
+                operation: BootCodeOperation::Output,
+                argument: _,
+            } => {
+                output.push(*accumulator);
+                increment_inst_counter(instruction_counter)
             }
-            .with_context(move || anyhow!("failed to execute instruction {:?}", instruction))
-        })()
-        .with_context(|| {
-            anyhow!(
-                "failed to execute next instruction; current state: {:?}",
-                self
-            )
-        })
+            BootCodeInstruction {
+                operation: BootCodeOperation::Halt,
+                argument: _,
+            } => {
+                *halted = true;
+                Ok(())
+            }
+        }
     }
 }
 
-fn parse_instructions(s: &str) -> anyhow::Result<Vec<BootCodeInstruction>> {
+/// A failure to parse or execute a boot code program.
+#[derive(Debug, thiserror::Error)]
+pub enum BootCodeError {
+    #[error("accumulator went out-of-range executing instruction {at}")]
+    AccumulatorOverflow { at: usize },
+    #[error("instruction counter went out-of-range executing instruction {at}")]
+    CounterOverflow { at: usize },
+    #[error("instruction counter {counter} is out-of-bounds for a program of {len} instructions")]
+    CounterOutOfBounds { counter: usize, len: usize },
+    #[error("emulator has already halted")]
+    AlreadyHalted,
+    #[error("failed to parse line {line}")]
+    Parse {
+        line: usize,
+        source: Box<dyn StdError + Send + Sync + 'static>,
+    },
+}
+
+#[test]
+fn accumulator_overflow() {
+    let program = parse_instructions(
+        "\
+nop +0
+acc +32767
+jmp -1
+",
+    )
+    .unwrap();
+
+    let mut emulator = BootCodeEmulator::new();
+    let error = loop {
+        if let Err(e) = emulator.step(&program) {
+            break e;
+        }
+    };
+    assert!(matches!(
+        error,
+        BootCodeError::AccumulatorOverflow { at: 1 }
+    ));
+    assert_eq!(emulator.accumulator(), i32::MAX / 32767 * 32767);
+
+    let mut emulator = BootCodeEmulator::new();
+    emulator.instruction_counter = 3;
+    assert!(matches!(
+        emulator.step(&program),
+        Err(BootCodeError::CounterOutOfBounds { counter: 3, len: 3 })
+    ));
+    assert!(matches!(
+        BootCodeEmulator::new().step(&parse_instructions("jmp -1").unwrap()),
+        Err(BootCodeError::CounterOverflow { at: 0 })
+    ));
+    assert!(matches!(
+        BootCodeEmulator::new().step(&parse_instructions("jmp -32768").unwrap()),
+        Err(BootCodeError::CounterOverflow { at: 0 })
+    ));
+    assert!(matches!(
+        parse_instructions("nop +0\nfoo +1"),
+        Err(BootCodeError::Parse { line: 2, .. })
+    ));
+}
+
+#[test]
+fn output_and_halt() {
+    let program = parse_instructions(
+        "\
+acc +5
+out +0
+hlt +0
+acc +1
+",
+    )
+    .unwrap();
+
+    let mut emulator = BootCodeEmulator::new();
+    (0..3).for_each(|_| emulator.step(&program).unwrap());
+    assert_eq!(emulator.output(), [5]);
+    assert!(emulator.is_halted());
+    assert_eq!(emulator.instruction_counter(), 2);
+    assert!(emulator.step(&program).is_err());
+
+    assert!(matches!(
+        run_until_halt(&program),
+        Termination::Terminated { accumulator: 5 }
+    ));
+}
+
+pub fn parse_instructions(s: &str) -> Result<Vec<BootCodeInstruction>, BootCodeError> {
     lines_without_endings(s)
         .zip(1..)
         .map(|(line, line_idx)| {
@@ -119,9 +353,11 @@ fn parse_instructions(s: &str) -> anyhow::Result<Vec<BootCodeInstruction>> {
                     .context("expected a space dividing ")?;
                 Ok(BootCodeInstruction {
                     operation: match raw_operation {
-                        "acc" => BootCodeOperation::Accumulate,
-                        "jmp" => BootCodeOperation::Jump,
-                        "nop" => BootCodeOperation::NoOp,
+                        BootCodeOperation::ACCUMULATE => BootCodeOperation::Accumulate,
+                        BootCodeOperation::JUMP => BootCodeOperation::Jump,
+                        BootCodeOperation::NO_OP => BootCodeOperation::NoOp,
+                        BootCodeOperation::OUTPUT => BootCodeOperation::Output,
+                        BootCodeOperation::HALT => BootCodeOperation::Halt,
                         _ => bail!("invalid operation {:?}", raw_operation),
                     },
                     argument: {
@@ -133,19 +369,87 @@ fn parse_instructions(s: &str) -> anyhow::Result<Vec<BootCodeInstruction>> {
                     },
                 })
             })()
-            .with_context(|| anyhow!("failed to parse line {}", line_idx))
+            .map_err(|e| BootCodeError::Parse {
+                line: line_idx,
+                source: e.into(),
+            })
         })
         .collect::<Result<Vec<_>, _>>()
 }
 
-fn part_1(s: &str) -> anyhow::Result<i32> {
-    let instructions = parse_instructions(s)?;
-    let mut emulator = BootCodeEmulator::zeroed();
+#[test]
+fn single_stepping() {
+    let program = parse_instructions(SAMPLE).unwrap();
+    let mut emulator = BootCodeEmulator::new();
+    [(1, 0), (2, 1), (6, 1)].iter().copied().for_each(
+        |(expected_counter, expected_accumulator)| {
+            emulator.step(&program).unwrap();
+            assert_eq!(emulator.instruction_counter(), expected_counter);
+            assert_eq!(emulator.accumulator(), expected_accumulator);
+        },
+    );
+}
+
+#[derive(Debug)]
+pub enum Termination {
+    /// The program was about to execute an instruction it had already executed.
+    Looped {
+        accumulator: i32,
+    },
+    /// The instruction counter landed just past the end of the program, or a `hlt` instruction
+    /// was executed.
+    Terminated {
+        accumulator: i32,
+    },
+    Error(BootCodeError),
+}
+
+/// Runs `program` from a fresh emulator until it either loops or terminates cleanly.
+pub fn run_until_halt(program: &[BootCodeInstruction]) -> Termination {
+    let mut emulator = BootCodeEmulator::new();
     let mut previously_seen_inst_counters = HashSet::new();
-    while previously_seen_inst_counters.insert(emulator.instruction_counter) {
-        emulator.execute_single_instruction(&instructions)?;
+    loop {
+        let instruction_counter = emulator.instruction_counter;
+        if emulator.halted || instruction_counter == program.len() {
+            break Termination::Terminated {
+                accumulator: emulator.accumulator,
+            };
+        }
+        if !previously_seen_inst_counters.insert(instruction_counter) {
+            break Termination::Looped {
+                accumulator: emulator.accumulator,
+            };
+        }
+        if let Err(e) = emulator.step(program) {
+            break Termination::Error(e);
+        }
+    }
+}
+
+#[test]
+fn termination() {
+    let mut program = parse_instructions(SAMPLE).unwrap();
+    assert!(matches!(
+        run_until_halt(&program),
+        Termination::Looped { accumulator: 5 }
+    ));
+
+    program[7].operation = BootCodeOperation::NoOp;
+    assert!(matches!(
+        run_until_halt(&program),
+        Termination::Terminated { accumulator: 8 }
+    ));
+}
+
+pub fn part_1(s: &str) -> anyhow::Result<i32> {
+    match run_until_halt(&parse_instructions(s)?) {
+        Termination::Looped { accumulator } => Ok(accumulator),
+        Termination::Terminated { accumulator } => bail!(
+            "expected program to loop, but it terminated with accumulator {}",
+            accumulator,
+        ),
+        Termination::Error(e) => Err(e.into()),
     }
-    Ok(emulator.accumulator)
 }
 
 #[test]
@@ -153,48 +457,43 @@ fn p1_answer() {
     assert_eq!(part_1(INPUT).unwrap(), 1801);
 }
 
-fn part_2(s: &str) -> anyhow::Result<i32> {
+/// Finds the first `nop`/`jmp` instruction whose flip makes the program terminate, returning its
+/// index along with the final accumulator.
+fn part_2_with_patch(s: &str) -> anyhow::Result<(usize, i32)> {
     let mut instructions = parse_instructions(s)?;
-    let changes_with_interesting_results = (0..instructions.len())
-        .filter_map(|change_idx| {
+    (0..instructions.len())
+        .find_map(|change_idx| {
             let original = instructions[change_idx].operation;
             let changed = match original {
-                BootCodeOperation::Accumulate => return None,
+                BootCodeOperation::Accumulate
+                | BootCodeOperation::Output
+                | BootCodeOperation::Halt => return None,
                 BootCodeOperation::NoOp => BootCodeOperation::Jump,
                 BootCodeOperation::Jump => BootCodeOperation::NoOp,
             };
 
             instructions[change_idx].operation = changed;
-            let mut previously_seen_inst_counters = HashSet::new();
-            let mut emulator = BootCodeEmulator::zeroed();
-            let filtered = loop {
-                let instruction_counter = emulator.instruction_counter;
-                if instruction_counter == instructions.len() {
-                    break Some(Ok((change_idx, emulator.accumulator)));
-                }
-                if !previously_seen_inst_counters.insert(instruction_counter) {
-                    break None;
-                }
-                if let Err(e) = emulator
-                    .execute_single_instruction(&instructions)
-                    .with_context(|| {
-                        anyhow!("replacing instruction {} yielded an error", change_idx)
-                    })
-                {
-                    break Some(Err(e));
-                }
+            let filtered = match run_until_halt(&instructions) {
+                Termination::Terminated { accumulator } => Some(Ok((change_idx, accumulator))),
+                Termination::Looped { .. } => None,
+                Termination::Error(e) => Some(Err(anyhow::Error::from(e).context(anyhow!(
+                    "replacing instruction {} yielded an error",
+                    change_idx
+                )))),
             };
             instructions[change_idx].operation = original;
             filtered
         })
-        .collect::<Vec<anyhow::Result<_>>>();
-
-    Ok(changes_with_interesting_results
-        .first()
-        .unwrap()
-        .as_ref()
-        .unwrap()
-        .1)
+        .context("no single instruction change made the program terminate")?
+}
+
+pub fn part_2(s: &str) -> anyhow::Result<i32> {
+    part_2_with_patch(s).map(|(_patched_idx, accumulator)| accumulator)
+}
+
+#[test]
+fn p2_sample_patch() {
+    assert_eq!(part_2_with_patch(SAMPLE).unwrap(), (7, 8));
 }
 
 #[test]
@@ -1,32 +1,16 @@
 use {
-    crate::parsing::lines_without_endings,
-    anyhow::{anyhow, Context},
-    std::cmp::Ordering,
+    crate::{
+        math::{pair_summing_to, subset_of_size_summing_to},
+        Solution,
+    },
+    anyhow::{anyhow, ensure, Context},
+    num_traits::CheckedAdd,
+    std::{
+        cmp::Ordering, error::Error as StdError, io::BufRead, ops::RangeInclusive, str::FromStr,
+    },
 };
 
-#[derive(Debug)]
-struct XmasEncryptedData {
-    data: Vec<u64>,
-    preamble_len: usize,
-}
-
-impl XmasEncryptedData {
-    fn parse(s: &str, preamble_len: usize) -> anyhow::Result<Self> {
-        Ok(Self {
-            data: lines_without_endings(s)
-                .map(|l| l.parse().context("failed to parse line"))
-                .collect::<anyhow::Result<Vec<_>>>()?,
-            preamble_len,
-        })
-    }
-
-    fn day_input() -> Self {
-        Self::parse(include_str!("d09.txt"), 25).expect("day 9 puzzle input should not be invalid")
-    }
-
-    fn sample() -> Self {
-        Self::parse(
-            "\
+const SAMPLE: &str = "\
 35
 20
 15
@@ -47,49 +31,104 @@ impl XmasEncryptedData {
 277
 309
 576
-",
-            5,
-        )
-        .expect("day 9 sample should not be invalid")
+";
+
+#[derive(Debug)]
+pub struct XmasEncryptedData<T = u64> {
+    data: Vec<T>,
+    preamble_len: usize,
+}
+
+impl<T> XmasEncryptedData<T>
+where
+    T: FromStr,
+    T::Err: StdError + Send + Sync + 'static,
+{
+    pub fn parse(s: &str, preamble_len: usize) -> anyhow::Result<Self> {
+        Self::from_reader(s.as_bytes(), preamble_len)
+    }
+
+    /// Like [`Self::parse`], but reads values from `r` one line at a time.
+    pub fn from_reader<R>(r: R, preamble_len: usize) -> anyhow::Result<Self>
+    where
+        R: BufRead,
+    {
+        ensure!(preamble_len > 0, "preamble length must be non-zero");
+        Ok(Self {
+            data: r
+                .lines()
+                .zip(1..)
+                .map(|(l, line_num)| -> anyhow::Result<T> {
+                    let l = l.with_context(|| anyhow!("failed to read line {}", line_num))?;
+                    l.parse()
+                        .with_context(|| anyhow!("failed to parse line {}", line_num))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            preamble_len,
+        })
+    }
+}
+
+impl XmasEncryptedData<u64> {
+    /// Parses puzzle input, which uses a preamble of 25 values.
+    pub fn from_input(s: &str) -> anyhow::Result<Self> {
+        Self::parse(s, 25)
     }
 
-    fn find_first_weakness(&self) -> Option<(usize, u64)> {
+    fn day_input() -> Self {
+        Self::from_input(include_str!("d09.txt")).expect("day 9 puzzle input should not be invalid")
+    }
+
+    fn sample() -> Self {
+        Self::parse(SAMPLE, 5).expect("day 9 sample should not be invalid")
+    }
+}
+
+impl<T> XmasEncryptedData<T>
+where
+    T: Copy + Ord + CheckedAdd,
+{
+    fn find_first_weakness(&self) -> Option<(usize, T)> {
+        self.find_first_weakness_k(2)
+    }
+
+    /// Finds the first value after the preamble that isn't the sum of `k` distinct values among
+    /// the `preamble_len` values before it.
+    pub fn find_first_weakness_k(&self, k: usize) -> Option<(usize, T)> {
         let &Self {
             ref data,
             preamble_len,
         } = self;
         data.windows(preamble_len)
             .zip(data.iter().copied().enumerate().skip(preamble_len))
-            .filter_map(|(previous_values, (next_check_idx, next_check_value))| {
-                let previous_values = previous_values.iter().copied();
-
-                let is_strong =
-                    previous_values
-                        .clone()
-                        .zip(1..)
-                        .any(|(augend, multiplier_start)| {
-                            previous_values
-                                .clone()
-                                .skip(multiplier_start)
-                                .find_map(|addend| {
-                                    augend
-                                        .checked_add(addend)
-                                        .filter(|&sum| sum == next_check_value)
-                                })
-                                .is_some()
-                        });
-
-                if is_strong {
-                    None
+            .find(|&(previous_values, (_next_check_idx, next_check_value))| {
+                let is_strong = if k == 2 {
+                    pair_summing_to(previous_values, next_check_value).is_some()
                 } else {
-                    Some((next_check_idx, next_check_value))
-                }
+                    subset_of_size_summing_to(previous_values, k, next_check_value).is_some()
+                };
+                !is_strong
             })
-            .next()
+            .map(|(_previous_values, next_check)| next_check)
+    }
+
+    /// Like [`Self::find_first_weakness`], but checks `target_values` instead of the values after
+    /// the preamble. Each target is checked against the window of `preamble_len` values starting
+    /// at the same index in the data, so the value at index `i` is checked against the `i`th
+    /// window. Targets beyond the last window are not checked.
+    pub fn first_non_sum(&self, target_values: &[T]) -> Option<(usize, T)> {
+        let &Self {
+            ref data,
+            preamble_len,
+        } = self;
+        data.windows(preamble_len)
+            .zip(target_values.iter().copied().enumerate())
+            .find(|&(window, (_target_idx, target))| pair_summing_to(window, target).is_none())
+            .map(|(_window, target)| target)
     }
 }
 
-fn part_1(encrypted_data: &XmasEncryptedData) -> anyhow::Result<(usize, u64)> {
+pub fn part_1(encrypted_data: &XmasEncryptedData) -> anyhow::Result<(usize, u64)> {
     encrypted_data
         .find_first_weakness()
         .context("no weak data found")
@@ -100,6 +139,61 @@ fn p1_sample() {
     assert_eq!(part_1(&XmasEncryptedData::sample()).unwrap(), (14, 127));
 }
 
+#[test]
+fn p1_sample_from_reader() {
+    let encrypted_data =
+        XmasEncryptedData::<u64>::from_reader(std::io::Cursor::new(SAMPLE.as_bytes()), 5).unwrap();
+    assert_eq!(part_1(&encrypted_data).unwrap(), (14, 127));
+
+    assert_eq!(
+        format!(
+            "{:#}",
+            XmasEncryptedData::<u64>::from_reader(std::io::Cursor::new(&b"1\n2\nthree\n"[..]), 5)
+                .unwrap_err()
+        ),
+        "failed to parse line 3: invalid digit found in string",
+    );
+}
+
+#[test]
+fn empty_preamble() {
+    assert_eq!(
+        XmasEncryptedData::<u64>::parse(SAMPLE, 0)
+            .unwrap_err()
+            .to_string(),
+        "preamble length must be non-zero",
+    );
+}
+
+#[test]
+fn p1_sample_u32() {
+    assert_eq!(
+        XmasEncryptedData::<u32>::parse(SAMPLE, 5)
+            .unwrap()
+            .find_first_weakness(),
+        Some((14, 127)),
+    );
+}
+
+#[test]
+fn weakness_with_three_addends() {
+    let encrypted_data = XmasEncryptedData::<u64>::parse("1\n2\n4\n7\n100\n", 3).unwrap();
+    assert_eq!(encrypted_data.find_first_weakness_k(2), Some((3, 7)));
+    assert_eq!(encrypted_data.find_first_weakness_k(3), Some((4, 100)));
+}
+
+#[test]
+fn sample_first_non_sum() {
+    let encrypted_data = XmasEncryptedData::sample();
+    assert_eq!(encrypted_data.first_non_sum(&[127]), Some((0, 127)));
+    assert_eq!(encrypted_data.first_non_sum(&[40, 127]), Some((1, 127)));
+    assert_eq!(encrypted_data.first_non_sum(&[40, 62]), None);
+    assert_eq!(
+        encrypted_data.first_non_sum(&encrypted_data.data[5..]),
+        Some((9, 127)),
+    );
+}
+
 #[test]
 fn p1_answer() {
     assert_eq!(
@@ -113,9 +207,11 @@ fn p2_sample() {
     assert_eq!(part_2(&XmasEncryptedData::sample()).unwrap(), (15, 47, 62))
 }
 
-fn part_2(encrypted_data: &XmasEncryptedData) -> anyhow::Result<(u64, u64, u64)> {
+/// Finds the index range of the first contiguous sequence of at least two values that adds up to
+/// the first weakness.
+fn part_2_range(encrypted_data: &XmasEncryptedData) -> anyhow::Result<RangeInclusive<usize>> {
     let (_weakness_idx, weakness_value) = part_1(encrypted_data)?;
-    let sequence = encrypted_data
+    encrypted_data
         .data
         .iter()
         .copied()
@@ -136,9 +232,7 @@ fn part_2(encrypted_data: &XmasEncryptedData) -> anyhow::Result<(u64, u64, u64)>
                         sum = new_sum;
                         match sum.cmp(&weakness_value) {
                             Ordering::Less => (),
-                            Ordering::Equal => {
-                                return Some(&encrypted_data.data[start_idx..=end_idx])
-                            }
+                            Ordering::Equal => return Some(start_idx..=end_idx),
                             Ordering::Greater => break,
                         }
                     }
@@ -152,10 +246,91 @@ fn part_2(encrypted_data: &XmasEncryptedData) -> anyhow::Result<(u64, u64, u64)>
                 "no contiguous sequence adding up to first weakness ({}) found",
                 weakness_value,
             )
-        })?;
+        })
+}
+
+#[test]
+fn p2_sample_range() {
+    let encrypted_data = XmasEncryptedData::sample();
+    let range = part_2_range(&encrypted_data).unwrap();
+    assert_eq!(range, 2..=5);
+    assert_eq!(encrypted_data.data[range].iter().sum::<u64>(), 127);
+}
+
+pub fn part_2(encrypted_data: &XmasEncryptedData) -> anyhow::Result<(u64, u64, u64)> {
+    let range = part_2_range(encrypted_data)?;
+    Ok(min_max_sum(&encrypted_data.data[range]))
+}
+
+fn min_max_sum(sequence: &[u64]) -> (u64, u64, u64) {
     let min = sequence.iter().copied().min().unwrap();
     let max = sequence.iter().copied().max().unwrap();
-    Ok((min, max, min + max))
+    (min, max, min + max)
+}
+
+/// Like [`part_2`], but finds the contiguous sequence with a single pass of a window over the data
+/// that grows its right edge while its sum is too small and shrinks its left edge while its sum is
+/// too large.
+fn part_2_sliding(encrypted_data: &XmasEncryptedData) -> anyhow::Result<(u64, u64, u64)> {
+    let (_weakness_idx, weakness_value) = part_1(encrypted_data)?;
+    let data = &encrypted_data.data;
+
+    let mut start_idx = 0;
+    let mut sum = 0u64;
+    for (end_idx, end) in data.iter().copied().enumerate() {
+        sum = sum
+            .checked_add(end)
+            .context("sum of contiguous sequence overflowed")?;
+        while sum > weakness_value && start_idx < end_idx {
+            sum -= data[start_idx];
+            start_idx += 1;
+        }
+        if sum == weakness_value && start_idx < end_idx {
+            return Ok(min_max_sum(&data[start_idx..=end_idx]));
+        }
+    }
+
+    Err(anyhow!(
+        "no contiguous sequence adding up to first weakness ({}) found",
+        weakness_value,
+    ))
+}
+
+#[test]
+fn p2_sliding() {
+    [XmasEncryptedData::sample(), XmasEncryptedData::day_input()]
+        .iter()
+        .for_each(|encrypted_data| {
+            assert_eq!(
+                part_2_sliding(encrypted_data).unwrap(),
+                part_2(encrypted_data).unwrap(),
+            );
+        });
+}
+
+pub struct Day09;
+
+impl Solution for Day09 {
+    type Parsed = XmasEncryptedData;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Parsed> {
+        XmasEncryptedData::from_input(input)
+    }
+
+    fn part_1(encrypted_data: &Self::Parsed) -> anyhow::Result<String> {
+        part_1(encrypted_data).map(|(_idx, weakness)| weakness.to_string())
+    }
+
+    fn part_2(encrypted_data: &Self::Parsed) -> anyhow::Result<String> {
+        part_2_sliding(encrypted_data).map(|(_min, _max, sum)| sum.to_string())
+    }
+}
+
+#[test]
+fn solution_trait() {
+    let encrypted_data = Day09::parse(include_str!("d09.txt")).unwrap();
+    assert_eq!(Day09::part_1(&encrypted_data).unwrap(), "69316178");
+    assert_eq!(Day09::part_2(&encrypted_data).unwrap(), "9351526");
 }
 
 #[test]
@@ -1,11 +1,8 @@
 use {
-    crate::parsing::lines_without_endings,
-    anyhow::{anyhow, ensure, Context},
-    std::{
-        convert::{TryFrom, TryInto},
-        ops::Deref,
-        str::FromStr,
-    },
+    crate::{parsing::lines_without_endings, Solution},
+    anyhow::{anyhow, bail, ensure, Context},
+    serde::Serialize,
+    std::{convert::TryFrom, ops::Deref, str::FromStr},
 };
 
 #[derive(Debug)]
@@ -18,18 +15,14 @@ impl FromStr for JoltageAdapterSet {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut adapters = lines_without_endings(s)
+        let adapters = lines_without_endings(s)
             .enumerate()
             .map(|(line_idx, l)| -> anyhow::Result<u16> {
                 l.parse::<u16>()
                     .with_context(|| anyhow!("failed to parse line {}", line_idx))
             })
             .collect::<anyhow::Result<Vec<_>>>()?;
-        ensure!(!adapters.is_empty(), "no adapters specified");
-        adapters.push(0);
-        adapters.sort_unstable();
-
-        Ok(Self(adapters))
+        Self::from_adapters(adapters)
     }
 }
 
@@ -42,9 +35,24 @@ impl Deref for JoltageAdapterSet {
 }
 
 impl JoltageAdapterSet {
-    #[track_caller]
+    /// Builds a set from the joltage ratings of `adapters`, in any order. Fails if there are no
+    /// adapters or if any rating (including the outlet's, `0`) is specified more than once.
+    pub fn from_adapters(adapters: impl IntoIterator<Item = u16>) -> anyhow::Result<Self> {
+        let mut adapters = adapters.into_iter().collect::<Vec<_>>();
+        ensure!(!adapters.is_empty(), "no adapters specified");
+        adapters.push(0);
+        adapters.sort_unstable();
+        if let Some(window) = adapters.windows(2).find(|window| window[0] == window[1]) {
+            bail!("adapter joltage {} is specified more than once", window[0]);
+        }
+
+        Ok(Self(adapters))
+    }
+
     fn joltage_flows_between_adapters(source: u16, target: u16) -> bool {
-        (1..=3).contains(&(target.checked_sub(source).unwrap()))
+        target
+            .checked_sub(source)
+            .map_or(false, |diff| (1..=3).contains(&diff))
     }
 
     pub fn connectable(&self) -> ConnectableJoltageAdapterSet<'_> {
@@ -53,92 +61,142 @@ impl JoltageAdapterSet {
             .zip(1..)
             .take_while(|&(window, _end_idx)| matches!(window, &[left, right] if Self::joltage_flows_between_adapters(left, right)))
             .map(|(_window, end_idx)| end_idx)
-            .last().unwrap();
+            .last()
+            .unwrap_or(0); // no adapter can take joltage from the outlet
 
         ConnectableJoltageAdapterSet(&self[1..=end_idx]) // we use `1` here because `0` will always be the first element
     }
 
-    /// Calculates the sum of the number of members of each power set of elements in runs of
-    /// optional adapter elements of this sequence.
-    pub fn num_valid_variants(&self) -> anyhow::Result<usize> {
-        // Alright, I had to look this one up. I still don't feel like I completely grok the theory
-        // behind it -- I understand the logic for generating cases for sequence possibility
-        // multiplication, but not _why_ that logic is valid.
-
-        pub struct PossibilityAccumulator {
-            last_skippable: u16,
-            num_consecutive_single_steps: usize,
-            num_possible_sequences: usize,
-        }
+    /// The largest difference in joltage between consecutive adapters, including the outlet.
+    pub fn longest_gap(&self) -> u16 {
+        self.windows(2)
+            .map(|window| window[1] - window[0])
+            .max()
+            .unwrap_or(0)
+    }
 
-        impl PossibilityAccumulator {
-            fn new() -> Self {
-                Self {
-                    last_skippable: 0,
-                    num_consecutive_single_steps: 0,
-                    num_possible_sequences: 1,
-                }
-            }
+    /// Whether [`Self::connectable`] includes every adapter in this set.
+    pub fn is_fully_connectable(&self) -> bool {
+        self.longest_gap() <= 3
+    }
 
-            fn on_break_single_step_skippable_streak(&mut self) -> anyhow::Result<()> {
-                let Self {
-                    last_skippable: _,
-                    num_consecutive_single_steps,
-                    num_possible_sequences,
-                } = self;
-
-                let naive_new_possibilities = (dbg!(*num_consecutive_single_steps))
-                    .try_into()
-                    .ok()
-                    .and_then(|steps| 2usize.checked_pow(steps))
-                    .context(
-                        "naive number of new possible sequences not representable with `usize`",
-                    )?;
-
-                *num_possible_sequences = (*num_possible_sequences)
-                    .checked_mul(dbg!(
-                        naive_new_possibilities - (naive_new_possibilities * 3 / 16)
-                    ))
-                    .context("accumulated possible sequences no representable with `usize`")?;
-                *num_consecutive_single_steps = 0;
-
-                Ok(())
-            }
+    /// The largest number of adapters (not counting the outlet) [`Self::valid_chains`] will
+    /// enumerate arrangements for.
+    pub const MAX_ENUMERABLE_ADAPTERS: usize = 20;
+
+    /// Enumerates every arrangement counted by [`Self::num_valid_variants`], each starting with the
+    /// outlet (`0`) and ending with the last adapter.
+    pub fn valid_chains(&self) -> anyhow::Result<Vec<Vec<u16>>> {
+        let last_idx = self.len() - 1; // also the number of adapters, since `0` is the outlet
+        ensure!(
+            last_idx <= Self::MAX_ENUMERABLE_ADAPTERS,
+            "refusing to enumerate arrangements of {} adapters; at most {} are supported",
+            last_idx,
+            Self::MAX_ENUMERABLE_ADAPTERS,
+        );
 
-            pub fn accumulate(&mut self, skippable: u16) -> anyhow::Result<()> {
-                if dbg!(self.last_skippable + 1) == dbg!(skippable) {
-                    self.num_consecutive_single_steps += 1;
-                } else {
-                    self.on_break_single_step_skippable_streak()?;
-                    self.num_consecutive_single_steps = 1;
-                };
+        let mut chains = Vec::new();
+        let mut partial_chains = vec![vec![0]];
+        while let Some(chain) = partial_chains.pop() {
+            let tail_idx = *chain.last().unwrap();
+            if tail_idx == last_idx {
+                chains.push(chain.into_iter().map(|idx| self[idx]).collect::<Vec<_>>());
+                continue;
+            }
+            let source = self[tail_idx];
+            self.iter()
+                .enumerate()
+                .skip(tail_idx + 1)
+                .take_while(|&(_idx, &target)| target - source <= 3)
+                .for_each(|(idx, _target)| {
+                    let mut next_chain = chain.clone();
+                    next_chain.push(idx);
+                    partial_chains.push(next_chain);
+                });
+        }
+        chains.sort_unstable();
+        Ok(chains)
+    }
 
-                self.last_skippable = skippable;
+    /// Counts the distinct arrangements of adapters that connect the outlet to the last adapter,
+    /// where each adapter in an arrangement is at most 3 jolts higher than the one before it.
+    pub fn num_valid_variants(&self) -> anyhow::Result<usize> {
+        // `ways[idx]` is the number of arrangements that end with the adapter at `idx`.
+        let mut ways = Vec::<usize>::with_capacity(self.len());
+        ways.push(1); // the outlet
+        for (idx, &target) in self.iter().enumerate().skip(1) {
+            let num_ways = self[..idx]
+                .iter()
+                .zip(&ways)
+                .rev()
+                .take_while(|&(&source, _ways)| target - source <= 3)
+                .try_fold(0usize, |acc, (_source, &ways)| acc.checked_add(ways))
+                .with_context(|| {
+                    anyhow!(
+                        "number of arrangements ending with adapter {} not representable with `usize`",
+                        idx
+                    )
+                })?;
+            ways.push(num_ways);
+        }
+        Ok(*ways.last().unwrap())
+    }
+}
 
-                Ok(())
+#[cfg(feature = "slow-tests")]
+impl JoltageAdapterSet {
+    /// Like [`Self::num_valid_variants`], but counts arrangements by recursing through every
+    /// adapter reachable from the outlet, memoizing the count of arrangements from each adapter
+    /// onwards.
+    pub fn num_valid_variants_bruteforce(&self) -> anyhow::Result<usize> {
+        fn count_from(
+            adapters: &[u16],
+            idx: usize,
+            memo: &mut [Option<usize>],
+        ) -> anyhow::Result<usize> {
+            if idx == adapters.len() - 1 {
+                return Ok(1);
+            }
+            if let Some(count) = memo[idx] {
+                return Ok(count);
             }
 
-            pub fn finished(mut self) -> anyhow::Result<usize> {
-                self.on_break_single_step_skippable_streak()
-                    .map(|()| self.num_possible_sequences)
+            let source = adapters[idx];
+            let mut count = 0usize;
+            for next_idx in
+                (idx + 1..adapters.len()).take_while(|&next_idx| adapters[next_idx] - source <= 3)
+            {
+                count = count
+                    .checked_add(count_from(adapters, next_idx, memo)?)
+                    .with_context(|| {
+                        anyhow!(
+                            "number of arrangements from adapter {} not representable with `usize`",
+                            idx
+                        )
+                    })?;
             }
+            memo[idx] = Some(count);
+            Ok(count)
         }
 
-        let mut acc = PossibilityAccumulator::new();
-        self.windows(3)
-            .filter_map(|window| {
-                let [left, mid, right] = <[_; 3]>::try_from(window).unwrap();
-                if Self::joltage_flows_between_adapters(left, right) {
-                    Some(mid)
-                } else {
-                    None
-                }
-            })
-            .try_for_each(|skippable| acc.accumulate(skippable))?;
-        acc.finished()
+        count_from(self, 0, &mut vec![None; self.len()])
     }
 }
 
+#[cfg(feature = "slow-tests")]
+#[test]
+fn p2_bruteforce() {
+    [FIRST_SAMPLE, SECOND_SAMPLE, "1\n2\n3\n4\n5", INPUT]
+        .iter()
+        .for_each(|s| {
+            let adapters = s.parse::<JoltageAdapterSet>().unwrap();
+            assert_eq!(
+                adapters.num_valid_variants_bruteforce().unwrap(),
+                adapters.num_valid_variants().unwrap(),
+            );
+        });
+}
+
 #[derive(Debug)]
 pub struct ConnectableJoltageAdapterSet<'a>(&'a [u16]);
 
@@ -151,34 +209,80 @@ impl Deref for ConnectableJoltageAdapterSet<'_> {
 }
 
 impl ConnectableJoltageAdapterSet<'_> {
-    fn diff_counts(&self) -> JoltDiffCounts {
+    pub(crate) fn diff_counts(&self) -> anyhow::Result<JoltDiffCounts> {
         let mut diff_counts = JoltDiffCounts {
             single: 0,
+            double: 0,
             triple: 1, // including the one at the end (AKA the laptop adapter)
         };
 
-        let mut accumulate_diff = |diff| match diff {
-            1 => diff_counts.single += 1,
-            2 => (),
-            3 => diff_counts.triple += 1,
-            _ => unreachable!(),
+        let mut accumulate_diff = |source: u16, target: u16| -> anyhow::Result<()> {
+            match target.checked_sub(source) {
+                Some(1) => diff_counts.single += 1,
+                Some(2) => diff_counts.double += 1,
+                Some(3) => diff_counts.triple += 1,
+                _ => bail!(
+                    "joltage difference from {} to {} is not between 1 and 3",
+                    source,
+                    target,
+                ),
+            }
+            Ok(())
         };
-        accumulate_diff(*self.first().unwrap());
-        self.windows(2).for_each(|window| {
+        accumulate_diff(0, *self.first().context("no connectable adapters")?)?;
+        self.windows(2).try_for_each(|window| {
             let [source, target] = <[_; 2]>::try_from(window).unwrap();
-            accumulate_diff(target - source)
-        });
+            accumulate_diff(source, target)
+        })?;
 
-        diff_counts
+        Ok(diff_counts)
     }
+
+    /// The joltage rating of the device's built-in adapter, which is 3 jolts higher than the
+    /// highest-rated adapter in the chain.
+    pub fn device_joltage(&self) -> anyhow::Result<u16> {
+        self.last()
+            .context("no connectable adapters")?
+            .checked_add(3)
+            .context("max joltage from adapters in bag not representable with u16")
+    }
+
+    /// Summarizes the joltage differences along the chain, including the product that answers
+    /// part 1.
+    pub fn chain_stats(&self) -> anyhow::Result<ChainStats> {
+        let diff_counts = self.diff_counts()?;
+        Ok(ChainStats {
+            single_diffs: diff_counts.single,
+            triple_diffs: diff_counts.triple,
+            part1_product: diff_counts.product()?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChainStats {
+    pub single_diffs: usize,
+    pub triple_diffs: usize,
+    /// `single_diffs * triple_diffs`
+    pub part1_product: usize,
 }
 
-#[derive(Debug, Eq, PartialEq)]
-struct JoltDiffCounts {
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct JoltDiffCounts {
     single: usize,
+    double: usize,
     triple: usize,
 }
 
+impl JoltDiffCounts {
+    /// Multiplies the number of 1-jolt differences by the number of 3-jolt differences.
+    fn product(&self) -> anyhow::Result<usize> {
+        self.single
+            .checked_mul(self.triple)
+            .context("diff count stat multiplication not representable")
+    }
+}
+
 const FIRST_SAMPLE: &str = "\
 16
 10
@@ -235,17 +339,13 @@ fn p1_samples() {
 
         let connectable_adapters = adapters.connectable();
 
-        let max_joltage = connectable_adapters
-            .last()
-            .unwrap()
-            .checked_add(3)
-            .context("max joltage from adapters in bag not representable with u16")
-            .unwrap();
-
-        assert_eq!(max_joltage, expected_max_joltage);
+        assert_eq!(
+            connectable_adapters.device_joltage().unwrap(),
+            expected_max_joltage
+        );
 
         assert_eq!(
-            connectable_adapters.diff_counts(),
+            connectable_adapters.diff_counts().unwrap(),
             expected_jolt_diff_counts,
         );
     }
@@ -254,6 +354,7 @@ fn p1_samples() {
         22,
         JoltDiffCounts {
             single: 7,
+            double: 0,
             triple: 5,
         },
     );
@@ -263,34 +364,118 @@ fn p1_samples() {
         52,
         JoltDiffCounts {
             single: 22,
+            double: 0,
             triple: 10,
         },
     );
 }
 
+#[test]
+fn duplicate_adapters() {
+    assert_eq!(
+        "1\n4\n2\n4"
+            .parse::<JoltageAdapterSet>()
+            .unwrap_err()
+            .to_string(),
+        "adapter joltage 4 is specified more than once",
+    );
+    assert!("0\n1".parse::<JoltageAdapterSet>().is_err());
+}
+
+#[test]
+fn from_adapters() {
+    let adapters = JoltageAdapterSet::from_adapters(vec![16, 10, 15, 5, 1]).unwrap();
+    assert_eq!(&*adapters, [0, 1, 5, 10, 15, 16]);
+    assert_eq!(&*adapters.connectable(), [1]);
+    assert_eq!(adapters.connectable().device_joltage().unwrap(), 4);
+
+    assert!(JoltageAdapterSet::from_adapters(vec![]).is_err());
+    assert!(JoltageAdapterSet::from_adapters(vec![3, 3]).is_err());
+}
+
+#[test]
+fn gaps() {
+    let adapters = FIRST_SAMPLE.parse::<JoltageAdapterSet>().unwrap();
+    assert!(adapters.is_fully_connectable());
+    assert_eq!(adapters.longest_gap(), 3);
+
+    let adapters = JoltageAdapterSet::from_adapters(vec![1, 2, 7, 8]).unwrap();
+    assert!(!adapters.is_fully_connectable());
+    assert_eq!(adapters.longest_gap(), 5);
+    assert_eq!(&*adapters.connectable(), [1, 2]);
+}
+
+#[test]
+fn unbridgeable_gap() {
+    let adapters = "1\n2\n6\n7".parse::<JoltageAdapterSet>().unwrap();
+    assert_eq!(&*adapters.connectable(), [1, 2]);
+    assert_eq!(adapters.connectable().device_joltage().unwrap(), 5);
+
+    let adapters = "4\n5".parse::<JoltageAdapterSet>().unwrap();
+    assert!(adapters.connectable().is_empty());
+    assert!(adapters.connectable().device_joltage().is_err());
+}
+
+#[test]
+fn diff_counts_with_gaps() {
+    let adapters = "1\n3\n4\n7\n9".parse::<JoltageAdapterSet>().unwrap();
+    assert_eq!(
+        adapters.connectable().diff_counts().unwrap(),
+        JoltDiffCounts {
+            single: 2,
+            double: 2,
+            triple: 2,
+        },
+    );
+
+    assert!(ConnectableJoltageAdapterSet(&[1, 1]).diff_counts().is_err());
+    assert!(ConnectableJoltageAdapterSet(&[1, 5]).diff_counts().is_err());
+}
+
 const INPUT: &str = include_str!("d10.txt");
 
+/// Multiplies the number of 1-jolt differences by the number of 3-jolt differences in the chain
+/// of connectable adapters.
+pub fn part_1(s: &str) -> anyhow::Result<usize> {
+    Ok(s.parse::<JoltageAdapterSet>()?
+        .connectable()
+        .chain_stats()?
+        .part1_product)
+}
+
 #[test]
 fn p1_answer() {
     let diff_counts = INPUT
         .parse::<JoltageAdapterSet>()
         .unwrap()
         .connectable()
-        .diff_counts();
+        .diff_counts()
+        .unwrap();
     assert_eq!(
         diff_counts,
         JoltDiffCounts {
             single: 72,
+            double: 0,
             triple: 36,
         }
     );
+    assert_eq!(part_1(INPUT).unwrap(), 2592);
+}
+
+#[test]
+fn p1_chain_stats() {
     assert_eq!(
-        diff_counts
-            .single
-            .checked_mul(diff_counts.triple)
-            .context("diff count stat multiplication not representable")
+        INPUT
+            .parse::<JoltageAdapterSet>()
+            .unwrap()
+            .connectable()
+            .chain_stats()
             .unwrap(),
-        2592 // 72 * 36
+        ChainStats {
+            single_diffs: 72,
+            triple_diffs: 36,
+            part1_product: 2592,
+        },
     );
 }
 
@@ -300,13 +485,49 @@ fn p2_sample() {
     assert_eq!(part_2(SECOND_SAMPLE).unwrap(), 19208);
 }
 
+#[test]
+fn p2_sample_chains() {
+    let adapters = FIRST_SAMPLE.parse::<JoltageAdapterSet>().unwrap();
+    let chains = adapters.valid_chains().unwrap();
+    assert_eq!(chains.len(), 8);
+    assert_eq!(chains.len(), adapters.num_valid_variants().unwrap());
+    assert_eq!(chains[0], [0, 1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19],);
+
+    assert!(INPUT
+        .parse::<JoltageAdapterSet>()
+        .unwrap()
+        .valid_chains()
+        .is_err());
+}
+
 #[test]
 fn p2_my_research() {
     assert_eq!(part_2("1\n2\n3\n4\n5").unwrap(), 13);
 }
 
-fn part_2(s: &str) -> anyhow::Result<usize> {
-    Ok(s.parse::<JoltageAdapterSet>()?.num_valid_variants()?)
+pub fn part_2(s: &str) -> anyhow::Result<usize> {
+    s.parse::<JoltageAdapterSet>()?.num_valid_variants()
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Parsed = JoltageAdapterSet;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Parsed> {
+        input.parse()
+    }
+
+    fn part_1(adapters: &Self::Parsed) -> anyhow::Result<String> {
+        adapters
+            .connectable()
+            .chain_stats()
+            .map(|stats| stats.part1_product.to_string())
+    }
+
+    fn part_2(adapters: &Self::Parsed) -> anyhow::Result<String> {
+        adapters.num_valid_variants().map(|n| n.to_string())
+    }
 }
 
 #[test]
@@ -1,10 +1,12 @@
 use {
     crate::parsing::lines_without_endings,
-    anyhow::{anyhow, ensure, Context},
+    anyhow::{anyhow, bail, ensure, Context},
     array_iterator::ArrayIterator,
     arrayvec::ArrayVec,
+    rayon::prelude::*,
     std::{
         cmp::min,
+        convert::TryFrom,
         fmt::{self, Display, Formatter},
         iter::successors,
         str::FromStr,
@@ -84,15 +86,53 @@ L.#.L..#..
     )
     .unwrap();
 
-    assert_eq!(
-        simulation
-            .current_state()
-            .tiles
-            .iter()
-            .filter(|tile| matches!(tile, WaitingAreaMapTile::Seat { occupied: true }))
-            .count(),
-        37,
-    );
+    assert_eq!(simulation.current_state().occupied_count(), 37);
+}
+
+/// A direction on a [`WaitingAreaMap`], where north is towards the first row and east is towards
+/// the end of each row.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction8 {
+    N,
+    S,
+    E,
+    W,
+    NE,
+    SE,
+    NW,
+    SW,
+}
+
+impl Direction8 {
+    /// Every direction, in order of [`Self::index`].
+    pub const ALL: [Self; 8] = [
+        Self::N,
+        Self::S,
+        Self::E,
+        Self::W,
+        Self::NE,
+        Self::SE,
+        Self::NW,
+        Self::SW,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The change in `(x, y)` from moving one tile in this direction.
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Self::N => (0, -1),
+            Self::S => (0, 1),
+            Self::E => (1, 0),
+            Self::W => (-1, 0),
+            Self::NE => (1, -1),
+            Self::SE => (1, 1),
+            Self::NW => (-1, -1),
+            Self::SW => (-1, 1),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -113,12 +153,7 @@ impl WaitingAreaMapTile {
     const FLOOR: char = '.';
 
     pub fn from_char(c: char) -> Option<Self> {
-        Some(match c {
-            Self::UNOCCUPIED_SEAT => WaitingAreaMapTile::Seat { occupied: false },
-            Self::OCCUPIED_SEAT => WaitingAreaMapTile::Seat { occupied: true },
-            Self::FLOOR => WaitingAreaMapTile::Floor,
-            _ => return None,
-        })
+        Self::try_from(c).ok()
     }
 
     pub fn as_char(self) -> char {
@@ -133,6 +168,151 @@ impl WaitingAreaMapTile {
             Self::Floor => Self::FLOOR,
         }
     }
+
+    /// Flips the occupancy of a seat. Floor is left as-is.
+    pub fn toggle(self) -> Self {
+        match self {
+            Self::Seat { occupied } => Self::Seat {
+                occupied: !occupied,
+            },
+            Self::Floor => Self::Floor,
+        }
+    }
+
+    pub fn is_occupied(self) -> bool {
+        self == Self::Seat { occupied: true }
+    }
+
+    pub fn is_seat(self) -> bool {
+        matches!(self, Self::Seat { .. })
+    }
+}
+
+#[test]
+fn tile_toggle_and_predicates() {
+    let empty = WaitingAreaMapTile::Seat { occupied: false };
+    let occupied = WaitingAreaMapTile::Seat { occupied: true };
+    let floor = WaitingAreaMapTile::Floor;
+
+    assert_eq!(empty.toggle(), occupied);
+    assert_eq!(occupied.toggle(), empty);
+    assert_eq!(floor.toggle(), floor);
+
+    assert_eq!(
+        [empty, occupied, floor]
+            .iter()
+            .map(|&tile| (tile.is_seat(), tile.is_occupied()))
+            .collect::<Vec<_>>(),
+        [(true, false), (true, true), (false, false)],
+    );
+}
+
+/// A character that doesn't represent any [`WaitingAreaMapTile`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, thiserror::Error)]
+#[error("unrecognized tile {0:?}")]
+pub struct InvalidTile(pub char);
+
+impl TryFrom<char> for WaitingAreaMapTile {
+    type Error = InvalidTile;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        Ok(match c {
+            Self::UNOCCUPIED_SEAT => WaitingAreaMapTile::Seat { occupied: false },
+            Self::OCCUPIED_SEAT => WaitingAreaMapTile::Seat { occupied: true },
+            Self::FLOOR => WaitingAreaMapTile::Floor,
+            _ => return Err(InvalidTile(c)),
+        })
+    }
+}
+
+#[test]
+fn tile_from_char() {
+    assert_eq!(
+        WaitingAreaMapTile::try_from('L'),
+        Ok(WaitingAreaMapTile::Seat { occupied: false }),
+    );
+    assert_eq!(
+        WaitingAreaMapTile::try_from('#'),
+        Ok(WaitingAreaMapTile::Seat { occupied: true }),
+    );
+    assert_eq!(WaitingAreaMapTile::try_from('x'), Err(InvalidTile('x')));
+    assert_eq!(WaitingAreaMapTile::from_char('x'), None);
+}
+
+impl FromStr for WaitingAreaMapTile {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Self::try_from(c)?),
+            _ => bail!("expected a single character, but got {:?}", s),
+        }
+    }
+}
+
+#[test]
+fn map_from_tiles() {
+    let map = WaitingAreaMap::from_tiles(vec![WaitingAreaMapTile::Floor; 4], 2).unwrap();
+    assert_eq!(map.to_string(), "..\n..\n");
+    assert_eq!(map, "..\n..\n".parse().unwrap());
+
+    assert!(WaitingAreaMap::from_tiles(vec![WaitingAreaMapTile::Floor; 4], 0).is_err());
+    assert!(WaitingAreaMap::from_tiles(vec![WaitingAreaMapTile::Floor; 4], 3).is_err());
+    assert!("\n\n".parse::<WaitingAreaMap>().is_err());
+}
+
+#[test]
+fn tile_and_map_parsing() {
+    assert_eq!(
+        "L".parse::<WaitingAreaMapTile>().unwrap(),
+        WaitingAreaMapTile::Seat { occupied: false },
+    );
+    assert!("?".parse::<WaitingAreaMapTile>().is_err());
+    assert!("LL".parse::<WaitingAreaMapTile>().is_err());
+    assert!("".parse::<WaitingAreaMapTile>().is_err());
+
+    let ragged_error = |s: &str| format!("{:#}", s.parse::<WaitingAreaMap>().unwrap_err());
+    assert_eq!(
+        ragged_error("L.L\nL.\nL.L\n"),
+        "failed to parse line 2: line 2 has width 2, expected 3",
+    );
+    assert_eq!(
+        ragged_error("L.L\nL.L#\n"),
+        "failed to parse line 2: line 2 has width 4, expected 3",
+    );
+}
+
+/// The number of tiles of each kind in a [`WaitingAreaMap`], which together add up to the number of
+/// tiles in the map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SeatCounts {
+    pub occupied: usize,
+    pub empty: usize,
+    pub floor: usize,
+}
+
+#[test]
+fn p1_sample_first_step_counts() {
+    let mut simulation =
+        WaitingAreaSeatingSimulation::new(SAMPLE.parse::<WaitingAreaMap>().unwrap());
+    let counts = simulation
+        .next_step(&mut Part1OccupantBehavior)
+        .unwrap()
+        .counts();
+    assert_eq!(
+        counts,
+        SeatCounts {
+            occupied: 71,
+            empty: 0,
+            floor: 29,
+        },
+    );
+    assert_eq!(
+        counts.occupied + counts.empty + counts.floor,
+        simulation.current_state().tiles().len(),
+    );
+    assert_eq!(simulation.current_state().tiles().len(), 100);
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -141,13 +321,82 @@ pub struct WaitingAreaMap {
     map_width: usize,
 }
 
+/// A tile that differs between two maps, as `(x, y, old, new)`.
+pub type TileChange = (usize, usize, WaitingAreaMapTile, WaitingAreaMapTile);
+
 impl WaitingAreaMap {
-    fn tiles(&self) -> &[WaitingAreaMapTile] {
+    /// Builds a map from `tiles` in row-major order, with `width` tiles per row.
+    pub fn from_tiles(tiles: Vec<WaitingAreaMapTile>, width: usize) -> anyhow::Result<Self> {
+        ensure!(width > 0, "map width must be non-zero");
+        ensure!(
+            tiles.len() % width == 0,
+            "{} tiles do not fill rows of width {}",
+            tiles.len(),
+            width,
+        );
+        Ok(Self {
+            tiles,
+            map_width: width,
+        })
+    }
+
+    /// Tiles of this map in row-major order.
+    pub fn tiles(&self) -> &[WaitingAreaMapTile] {
         &self.tiles
     }
 
+    pub fn occupied_count(&self) -> usize {
+        self.counts().occupied
+    }
+
+    /// Tallies each kind of tile in this map.
+    pub fn counts(&self) -> SeatCounts {
+        let mut counts = SeatCounts {
+            occupied: 0,
+            empty: 0,
+            floor: 0,
+        };
+        self.tiles.iter().for_each(|tile| match tile {
+            WaitingAreaMapTile::Seat { occupied: true } => counts.occupied += 1,
+            WaitingAreaMapTile::Seat { occupied: false } => counts.empty += 1,
+            WaitingAreaMapTile::Floor => counts.floor += 1,
+        });
+        counts
+    }
+
+    /// The number of occupied seats among the tiles immediately surrounding the one at `offset`.
+    pub fn occupied_adjacent_count(&self, offset: usize) -> usize {
+        self.get_adjacent_tiles(offset)
+            .filter(|tile| tile.is_occupied())
+            .count()
+    }
+
+    /// Like [`Self::occupied_adjacent_count`], but treats the map as a torus.
+    pub fn occupied_wrapping_adjacent_count(&self, offset: usize) -> usize {
+        self.get_adjacent_tiles_wrapping(offset)
+            .filter(|tile| tile.is_occupied())
+            .count()
+    }
+
+    /// The number of occupied seats among the first seats visible in each direction from the tile
+    /// at `offset`.
+    pub fn occupied_visible_count(&self, offset: usize) -> usize {
+        self.get_visible_seats(offset)
+            .filter(|&occupied| occupied)
+            .count()
+    }
+
+    /// Iterates over the tiles surrounding the one at `offset`, without crossing the edges of the
+    /// map. Corner tiles have 3 neighbors, other edge tiles have 5, and interior tiles have 8.
     fn get_adjacent_tiles(&self, offset: usize) -> impl Iterator<Item = WaitingAreaMapTile> + '_ {
-        let mut areas = ArrayVec::<[WaitingAreaMapTile; 9]>::new();
+        self.adjacent_offsets(offset)
+            .into_iter()
+            .map(move |o| self.tiles[o])
+    }
+
+    /// Offsets of the tiles yielded by [`Self::get_adjacent_tiles`].
+    fn adjacent_offsets(&self, offset: usize) -> ArrayVec<[usize; 8]> {
+        let mut offsets = ArrayVec::new();
 
         let &Self {
             map_width: width,
@@ -169,85 +418,213 @@ impl WaitingAreaMap {
 
         if let Some(top_adjacent_area) = offset.checked_sub(width).map(gather_window_with_center_at)
         {
-            areas.extend(tiles[top_adjacent_area].iter().copied());
+            offsets.extend(top_adjacent_area);
         }
 
-        gather_window_with_center_at(offset)
-            .filter(|&o| o != offset)
-            .for_each(|o| {
-                areas.push(tiles[o]);
-            });
+        offsets.extend(gather_window_with_center_at(offset).filter(|&o| o != offset));
 
         if let Some(bottom_adjacent_area) = offset
             .checked_add(width)
             .filter(|&o| o < tiles.len())
             .map(gather_window_with_center_at)
         {
-            areas.extend(tiles[bottom_adjacent_area].iter().copied());
+            offsets.extend(bottom_adjacent_area);
         }
 
-        areas.into_iter()
+        offsets
+    }
+
+    /// Computes [`Self::adjacent_offsets`] for every tile on this map. Since tiles never move,
+    /// this only needs to be done once per map layout.
+    pub fn precompute_adjacent_neighbors(&self) -> Vec<ArrayVec<[usize; 8]>> {
+        (0..self.tiles.len())
+            .map(|offset| self.adjacent_offsets(offset))
+            .collect()
+    }
+
+    /// Like [`Self::get_adjacent_tiles`], but treats the map as a torus, so every tile has exactly
+    /// 8 neighbors. On maps fewer than 3 tiles wide or tall, some of these are the same tile,
+    /// possibly even the one at `offset`.
+    fn get_adjacent_tiles_wrapping(
+        &self,
+        offset: usize,
+    ) -> impl Iterator<Item = WaitingAreaMapTile> + '_ {
+        self.wrapping_adjacent_offsets(offset)
+            .into_iter()
+            .map(move |o| self.tiles[o])
+    }
+
+    /// Offsets of the tiles yielded by [`Self::get_adjacent_tiles_wrapping`].
+    fn wrapping_adjacent_offsets(&self, offset: usize) -> ArrayVec<[usize; 8]> {
+        let &Self {
+            map_width: width,
+            ref tiles,
+        } = self;
+
+        let area = tiles.len();
+        let height = area / width;
+        let WaitingAreaMapCoords { x, y } = Self::translate_offset_into_human_coords(offset, width);
+
+        // Moving backwards by one is the same as moving forwards by one less than the length.
+        let (left, right) = (width - 1, 1);
+        let (up, down) = (height - 1, 1);
+
+        ArrayIterator::new([
+            (left, up),
+            (0, up),
+            (right, up),
+            (left, 0),
+            (right, 0),
+            (left, down),
+            (0, down),
+            (right, down),
+        ])
+        .map(|(dx, dy)| {
+            Self::translate_human_coords_into_offset(
+                WaitingAreaMapCoords {
+                    x: (x + dx) % width,
+                    y: (y + dy) % height,
+                },
+                width,
+                area,
+            )
+        })
+        .collect()
+    }
+
+    /// Computes [`Self::wrapping_adjacent_offsets`] for every tile on this map.
+    pub fn precompute_wrapping_adjacent_neighbors(&self) -> Vec<ArrayVec<[usize; 8]>> {
+        (0..self.tiles.len())
+            .map(|offset| self.wrapping_adjacent_offsets(offset))
+            .collect()
     }
 
     fn get_visible_seats(&self, offset: usize) -> impl Iterator<Item = bool> + '_ {
+        self.visible_seat_offsets(offset)
+            .map(move |o| self.tiles[o].is_occupied())
+    }
+
+    /// Offsets of the seats whose occupancy is yielded by [`Self::get_visible_seats`].
+    fn visible_seat_offsets(&self, offset: usize) -> impl Iterator<Item = usize> + '_ {
+        assert!(offset < self.tiles.len());
+        ArrayIterator::new(Direction8::ALL)
+            .filter_map(move |direction| self.first_visible_seat_offset(offset, direction))
+    }
+
+    /// The offset of the first seat visible from the tile at `offset` when looking in `direction`.
+    fn first_visible_seat_offset(&self, offset: usize, direction: Direction8) -> Option<usize> {
         let &Self {
             map_width,
             ref tiles,
         } = self;
 
         let area = tiles.len();
-        assert!(offset < area);
+        let map_height = area / map_width;
+        let WaitingAreaMapCoords { x, y } =
+            Self::translate_offset_into_human_coords(offset, map_width);
 
-        #[derive(Clone, Copy, Debug)]
-        enum OffsetOp {
-            PlusOne,
-            NegOne,
-        }
+        let (dx, dy) = direction.delta();
+        let step = |pos: usize, delta: isize, len: usize| {
+            (pos as isize)
+                .checked_add(delta)
+                .and_then(|pos| usize::try_from(pos).ok())
+                .filter(|&pos| pos < len)
+        };
 
-        impl OffsetOp {
-            fn apply(self, pos: usize) -> Option<usize> {
-                match self {
-                    Self::PlusOne => pos.checked_add(1),
-                    Self::NegOne => pos.checked_sub(1),
-                }
-            }
+        successors(Some((x, y)), |&(x, y)| {
+            Some((step(x, dx, map_width)?, step(y, dy, map_height)?))
+        })
+        .skip(1)
+        .map(|(x, y)| {
+            Self::translate_human_coords_into_offset(WaitingAreaMapCoords { x, y }, map_width, area)
+        })
+        .find(|&offset| tiles[offset].is_seat())
+    }
+
+    /// Like [`Self::get_visible_seats`], but indexed by [`Direction8::index`], with `None` for
+    /// directions in which no seat is visible.
+    pub fn visible_seats_by_direction(&self, offset: usize) -> [Option<bool>; 8] {
+        let mut seats = [None; 8];
+        Direction8::ALL.iter().for_each(|&direction| {
+            seats[direction.index()] = self
+                .first_visible_seat_offset(offset, direction)
+                .map(|o| self.tiles[o].is_occupied());
+        });
+        seats
+    }
+
+    /// Computes [`Self::visible_seat_offsets`] for every tile on this map. Since seats never move,
+    /// this only needs to be done once per map layout.
+    pub fn precompute_visible_neighbors(&self) -> Vec<ArrayVec<[usize; 8]>> {
+        (0..self.tiles.len())
+            .map(|offset| self.visible_seat_offsets(offset).collect())
+            .collect()
+    }
+
+    /// Lists `(x, y, old, new)` for each tile that differs between this map (`old`) and `other`
+    /// (`new`), in row-major order.
+    pub fn diff(&self, other: &WaitingAreaMap) -> anyhow::Result<Vec<TileChange>> {
+        ensure!(
+            self.map_width == other.map_width && self.tiles.len() == other.tiles.len(),
+            "cannot diff a map of {} tiles with width {} against a map of {} tiles with width {}",
+            self.tiles.len(),
+            self.map_width,
+            other.tiles.len(),
+            other.map_width,
+        );
+        Ok(self
+            .iter_with_coords()
+            .zip(other.tiles.iter().copied())
+            .filter(|&((_x, _y, old), new)| old != new)
+            .map(|((x, y, old), new)| (x, y, old, new))
+            .collect())
+    }
+
+    fn offset_of(&self, x: usize, y: usize) -> Option<usize> {
+        let &Self {
+            ref tiles,
+            map_width,
+        } = self;
+        if x < map_width && y < tiles.len() / map_width {
+            Some(Self::translate_human_coords_into_offset(
+                WaitingAreaMapCoords { x, y },
+                map_width,
+                tiles.len(),
+            ))
+        } else {
+            None
         }
+    }
 
-        let WaitingAreaMapCoords { x, y } =
-            Self::translate_offset_into_human_coords(offset, map_width);
-        let map_height = area / map_width;
+    /// The tile at column `x` and row `y`, or `None` if that's outside of this map.
+    pub fn get(&self, x: usize, y: usize) -> Option<WaitingAreaMapTile> {
+        self.offset_of(x, y).map(|offset| self.tiles[offset])
+    }
 
-        ArrayIterator::new([
-            (None, Some(OffsetOp::PlusOne)),                    // up
-            (None, Some(OffsetOp::NegOne)),                     // down
-            (Some(OffsetOp::PlusOne), None),                    // right
-            (Some(OffsetOp::NegOne), None),                     // left
-            (Some(OffsetOp::PlusOne), Some(OffsetOp::PlusOne)), // up-right
-            (Some(OffsetOp::PlusOne), Some(OffsetOp::NegOne)),  // down-right
-            (Some(OffsetOp::NegOne), Some(OffsetOp::PlusOne)),  // up-left
-            (Some(OffsetOp::NegOne), Some(OffsetOp::NegOne)),   // down-left
-        ])
-        .filter_map(move |(ox, oy)| {
-            successors(Some((x, y)), |&(x, y)| {
-                Some((
-                    ox.map(|ox| ox.apply(x).filter(|&x| x < map_width))
-                        .unwrap_or(Some(x))?,
-                    oy.map(|oy| oy.apply(y).filter(|&y| y < map_height))
-                        .unwrap_or(Some(y))?,
-                ))
-            })
-            .skip(1)
-            .find_map(|(x, y)| {
-                let offset = Self::translate_human_coords_into_offset(
-                    WaitingAreaMapCoords { x, y },
-                    map_width,
-                    area,
-                );
-                match tiles[offset] {
-                    WaitingAreaMapTile::Seat { occupied } => Some(occupied),
-                    _ => None,
-                }
-            })
+    /// Replaces the tile at column `x` and row `y`.
+    pub fn set(&mut self, x: usize, y: usize, tile: WaitingAreaMapTile) -> anyhow::Result<()> {
+        let offset = self.offset_of(x, y).with_context(|| {
+            anyhow!(
+                "coordinates ({}, {}) are outside of a {}x{} map",
+                x,
+                y,
+                self.map_width,
+                self.tiles.len() / self.map_width,
+            )
+        })?;
+        self.tiles[offset] = tile;
+        Ok(())
+    }
+
+    /// Iterates over every tile in row-major order, along with its `x` and `y` coordinates.
+    pub fn iter_with_coords(
+        &self,
+    ) -> impl Iterator<Item = (usize, usize, WaitingAreaMapTile)> + '_ {
+        let width = self.map_width;
+        self.tiles.iter().enumerate().map(move |(offset, &tile)| {
+            let WaitingAreaMapCoords { x, y } =
+                Self::translate_offset_into_human_coords(offset, width);
+            (x, y, tile)
         })
     }
 
@@ -274,8 +651,214 @@ impl WaitingAreaMap {
     }
 }
 
-impl Display for WaitingAreaMap {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+#[test]
+fn tiles_with_coords() {
+    let map = "L.#\n#L.\n".parse::<WaitingAreaMap>().unwrap();
+    let empty = WaitingAreaMapTile::Seat { occupied: false };
+    let occupied = WaitingAreaMapTile::Seat { occupied: true };
+    let floor = WaitingAreaMapTile::Floor;
+    assert_eq!(
+        map.iter_with_coords().collect::<Vec<_>>(),
+        [
+            (0, 0, empty),
+            (1, 0, floor),
+            (2, 0, occupied),
+            (0, 1, occupied),
+            (1, 1, empty),
+            (2, 1, floor),
+        ],
+    );
+    map.iter_with_coords()
+        .for_each(|(x, y, tile)| assert_eq!(map.get(x, y), Some(tile)));
+}
+
+#[test]
+fn p1_sample_diff() {
+    let first = "\
+#.##.##.##
+#######.##
+#.#.#..#..
+####.##.##
+#.##.##.##
+#.#####.##
+..#.#.....
+##########
+#.######.#
+#.#####.##
+"
+    .parse::<WaitingAreaMap>()
+    .unwrap();
+    let second = "\
+#.LL.L#.##
+#LLLLLL.L#
+L.L.L..L..
+#LLL.LL.L#
+#.LL.LL.LL
+#.LLLL#.##
+..L.L.....
+#LLLLLLLL#
+#.LLLLLL.L
+#.#LLLL.##
+"
+    .parse::<WaitingAreaMap>()
+    .unwrap();
+
+    let changes = first.diff(&second).unwrap();
+    assert_eq!(changes.len(), 51);
+    assert_eq!(
+        changes
+            .iter()
+            .take(3)
+            .map(|&(x, y, _old, _new)| (x, y))
+            .collect::<Vec<_>>(),
+        [(2, 0), (3, 0), (5, 0)],
+    );
+    assert!(changes.iter().all(|&(_x, _y, old, new)| {
+        old == WaitingAreaMapTile::Seat { occupied: true }
+            && new == WaitingAreaMapTile::Seat { occupied: false }
+    }));
+
+    assert!(first.diff(&"L.\n".parse().unwrap()).is_err());
+}
+
+#[test]
+fn get_and_set_tiles() {
+    let mut map = "L.#\n#L.\n".parse::<WaitingAreaMap>().unwrap();
+    let occupied = WaitingAreaMapTile::Seat { occupied: true };
+
+    assert_eq!(map.get(1, 0), Some(WaitingAreaMapTile::Floor));
+    map.set(1, 0, occupied).unwrap();
+    assert_eq!(map.get(1, 0), Some(occupied));
+    assert_eq!(map.to_string(), "L##\n#L.\n");
+
+    assert_eq!(map.get(3, 0), None);
+    assert_eq!(map.get(0, 2), None);
+    assert_eq!(
+        map.set(0, 2, occupied).unwrap_err().to_string(),
+        "coordinates (0, 2) are outside of a 3x2 map",
+    );
+}
+
+#[test]
+fn adjacent_tiles_at_edges() {
+    let map = "\
+LLLL
+LLLL
+LLLL
+LLLL
+"
+    .parse::<WaitingAreaMap>()
+    .unwrap();
+    assert_eq!(
+        (0..map.tiles().len())
+            .map(|offset| map.get_adjacent_tiles(offset).count())
+            .collect::<Vec<_>>(),
+        [
+            3, 5, 5, 3, //
+            5, 8, 8, 5, //
+            5, 8, 8, 5, //
+            3, 5, 5, 3, //
+        ],
+    );
+
+    // Neighbors of the last column must not include the first column of adjacent rows, and vice
+    // versa.
+    let map = "\
+#..#
+#..#
+.#.#
+"
+    .parse::<WaitingAreaMap>()
+    .unwrap();
+    assert_eq!(
+        (0..map.tiles().len())
+            .map(|offset| map
+                .get_adjacent_tiles(offset)
+                .filter(|tile| tile.is_occupied())
+                .count())
+            .collect::<Vec<_>>(),
+        [
+            1, 2, 2, 1, //
+            2, 3, 4, 2, //
+            2, 1, 3, 1, //
+        ],
+    );
+}
+
+#[test]
+fn wrapping_adjacent_tiles() {
+    let count_tiles = |tiles: &mut dyn Iterator<Item = WaitingAreaMapTile>| {
+        tiles.fold([0; 3], |[floor, empty, occupied], tile| match tile {
+            WaitingAreaMapTile::Floor => [floor + 1, empty, occupied],
+            WaitingAreaMapTile::Seat { occupied: false } => [floor, empty + 1, occupied],
+            WaitingAreaMapTile::Seat { occupied: true } => [floor, empty, occupied + 1],
+        })
+    };
+
+    let map = "\
+#.L
+.L.
+L.#
+"
+    .parse::<WaitingAreaMap>()
+    .unwrap();
+    assert_eq!(count_tiles(&mut map.get_adjacent_tiles(0)), [2, 1, 0]);
+    assert_eq!(
+        count_tiles(&mut map.get_adjacent_tiles_wrapping(0)),
+        [4, 3, 1]
+    );
+
+    let map = "#".parse::<WaitingAreaMap>().unwrap();
+    assert_eq!(
+        count_tiles(&mut map.get_adjacent_tiles_wrapping(0)),
+        [0, 0, 8]
+    );
+}
+
+/// Glyphs to render each kind of [`WaitingAreaMapTile`] with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TileChars {
+    pub occupied: char,
+    pub empty: char,
+    pub floor: char,
+}
+
+impl TileChars {
+    /// The glyphs used by puzzle input, and by [`WaitingAreaMap`]'s `Display` impl.
+    pub const CANONICAL: Self = Self {
+        occupied: WaitingAreaMapTile::OCCUPIED_SEAT,
+        empty: WaitingAreaMapTile::UNOCCUPIED_SEAT,
+        floor: WaitingAreaMapTile::FLOOR,
+    };
+
+    fn get(self, tile: WaitingAreaMapTile) -> char {
+        match tile {
+            WaitingAreaMapTile::Seat { occupied: true } => self.occupied,
+            WaitingAreaMapTile::Seat { occupied: false } => self.empty,
+            WaitingAreaMapTile::Floor => self.floor,
+        }
+    }
+}
+
+impl Default for TileChars {
+    fn default() -> Self {
+        Self::CANONICAL
+    }
+}
+
+impl WaitingAreaMap {
+    /// Renders this map like its `Display` impl does, but with glyphs from `chars`.
+    pub fn render_with(&self, chars: TileChars) -> String {
+        let mut rendered =
+            String::with_capacity(self.tiles.len() + self.tiles.len() / self.map_width);
+        self.write_with(&mut rendered, chars).unwrap();
+        rendered
+    }
+
+    fn write_with<W>(&self, w: &mut W, chars: TileChars) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
         let &Self {
             ref tiles,
             map_width,
@@ -284,13 +867,32 @@ impl Display for WaitingAreaMap {
         tiles.chunks(map_width).try_for_each(|chunk| {
             chunk
                 .iter()
-                .copied()
-                .try_for_each(|tile| write!(f, "{}", tile))?;
-            writeln!(f)
+                .try_for_each(|&tile| w.write_char(chars.get(tile)))?;
+            writeln!(w)
         })
     }
 }
 
+impl Display for WaitingAreaMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_with(f, TileChars::CANONICAL)
+    }
+}
+
+#[test]
+fn render_with_custom_glyphs() {
+    let map = "#L.\n.#L\n".parse::<WaitingAreaMap>().unwrap();
+    assert_eq!(
+        map.render_with(TileChars {
+            occupied: 'O',
+            empty: '-',
+            floor: '_',
+        }),
+        "O-_\n_O-\n",
+    );
+    assert_eq!(map.render_with(TileChars::default()), map.to_string());
+}
+
 impl FromStr for WaitingAreaMap {
     type Err = anyhow::Error;
 
@@ -303,43 +905,68 @@ impl FromStr for WaitingAreaMap {
             .count();
         let tiles = map_string_lines
             .zip(1..)
-            .flat_map(|(line, line_idx)| {
-                line.char_indices()
-                    .enumerate()
-                    .map(|(count, (idx, c))| {
-                        ensure!(
-                            count < expected_row_width,
-                            "line's character count exceeds the one found on first line ({}), \
-                            producing incomplete map dimensions",
-                            expected_row_width,
-                        );
-
-                        Ok(WaitingAreaMapTile::from_char(c).with_context(|| {
+            .flat_map(|(line, line_num)| {
+                let line_width = line.chars().count();
+                let width_mismatch = if line_width == expected_row_width {
+                    None
+                } else {
+                    Some(Err(anyhow!(
+                        "line {} has width {}, expected {}",
+                        line_num,
+                        line_width,
+                        expected_row_width,
+                    )))
+                };
+                width_mismatch
+                    .into_iter()
+                    .chain(line.char_indices().enumerate().map(|(count, (idx, c))| {
+                        WaitingAreaMapTile::from_char(c).with_context(|| {
                             anyhow!(
                                 "unrecognized value {:?} for character {} (byte index {})",
                                 c,
                                 count,
                                 idx,
                             )
-                        })?)
-                    })
+                        })
+                    }))
                     .map(move |res| {
-                        res.with_context(move || anyhow!("failed to parse line {}", line_idx))
+                        res.with_context(move || anyhow!("failed to parse line {}", line_num))
                     })
             })
             .collect::<anyhow::Result<Vec<_>>>()?;
 
-        Ok(Self {
-            tiles,
-            map_width: expected_row_width,
-        })
+        Self::from_tiles(tiles, expected_row_width)
     }
 }
 
+/// A double-buffered simulation of people sitting down and leaving seats in a waiting area.
+///
+/// ```
+/// use advent_of_code_2020::days::d11::{
+///     Part1OccupantBehavior, WaitingAreaMap, WaitingAreaMapTile, WaitingAreaSeatingSimulation,
+/// };
+///
+/// let map = "\
+/// L.L
+/// LLL
+/// "
+/// .parse::<WaitingAreaMap>()
+/// .unwrap();
+/// let mut simulation = WaitingAreaSeatingSimulation::new(map);
+///
+/// let next_map = simulation.next_step(Part1OccupantBehavior).unwrap();
+/// assert_eq!(next_map.occupied_count(), 5);
+/// assert!(next_map
+///     .tiles()
+///     .iter()
+///     .all(|&tile| tile != WaitingAreaMapTile::Seat { occupied: false }));
+/// ```
 #[derive(Clone, Debug)]
-struct WaitingAreaSeatingSimulation {
+pub struct WaitingAreaSeatingSimulation {
     map_copies: [WaitingAreaMap; 2],
     curr_map_idx: usize,
+    initial_map: WaitingAreaMap,
+    steps: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -348,7 +975,7 @@ struct WaitingAreaMapCoords {
     y: usize,
 }
 
-trait WaitingAreaOccupantBehavior {
+pub trait WaitingAreaOccupantBehavior {
     fn would_enter_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool;
     fn would_leave_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool;
 }
@@ -369,25 +996,78 @@ where
 impl WaitingAreaSeatingSimulation {
     pub fn new(starting_map: WaitingAreaMap) -> Self {
         Self {
-            map_copies: [starting_map.clone(), starting_map],
+            map_copies: [starting_map.clone(), starting_map.clone()],
             curr_map_idx: 0,
+            initial_map: starting_map,
+            steps: 0,
         }
     }
 
-    fn next_step<B>(&mut self, mut occupant_behavior: B) -> Option<&WaitingAreaMap>
-    where
-        B: WaitingAreaOccupantBehavior,
-    {
+    /// Restores the map this simulation started with, as if no steps had been run.
+    pub fn reset(&mut self) {
+        let Self {
+            map_copies,
+            curr_map_idx,
+            initial_map,
+            steps,
+        } = self;
+        map_copies
+            .iter_mut()
+            .for_each(|map| map.clone_from(initial_map));
+        *curr_map_idx = 0;
+        *steps = 0;
+    }
+
+    /// The number of steps that have changed the map since this simulation started or was last
+    /// [reset](Self::reset).
+    pub fn step_count(&self) -> usize {
+        self.steps
+    }
+
+    fn split_maps_mut(&mut self) -> (&WaitingAreaMap, (usize, &mut WaitingAreaMap)) {
         let &mut Self {
             curr_map_idx,
             map_copies: [ref mut first_map, ref mut second_map],
+            ..
         } = self;
 
-        let (prev_map, (next_map_idx, next_map)) = match curr_map_idx {
-            0 => (&first_map, (1, second_map)),
-            1 => (&second_map, (0, first_map)),
+        match curr_map_idx {
+            0 => (first_map, (1, second_map)),
+            1 => (second_map, (0, first_map)),
             _ => unreachable!(),
-        };
+        }
+    }
+
+    /// Computes what the tile at `idx` becomes after one step, or `None` if it stays the same.
+    fn next_tile<B>(
+        occupant_behavior: &mut B,
+        prev_map: &WaitingAreaMap,
+        idx: usize,
+        prev_tile: WaitingAreaMapTile,
+    ) -> Option<WaitingAreaMapTile>
+    where
+        B: WaitingAreaOccupantBehavior,
+    {
+        match prev_tile {
+            WaitingAreaMapTile::Seat { occupied: false }
+                if occupant_behavior.would_enter_seat(prev_map, idx) =>
+            {
+                Some(WaitingAreaMapTile::Seat { occupied: true })
+            }
+            WaitingAreaMapTile::Seat { occupied: true }
+                if occupant_behavior.would_leave_seat(prev_map, idx) =>
+            {
+                Some(WaitingAreaMapTile::Seat { occupied: false })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn next_step<B>(&mut self, mut occupant_behavior: B) -> Option<&WaitingAreaMap>
+    where
+        B: WaitingAreaOccupantBehavior,
+    {
+        let (prev_map, (next_map_idx, next_map)) = self.split_maps_mut();
 
         let mut changed = false;
         prev_map
@@ -396,25 +1076,76 @@ impl WaitingAreaSeatingSimulation {
             .zip(next_map.tiles.iter_mut())
             .enumerate()
             .for_each(|(idx, (&prev_tile, next_tile))| {
-                *next_tile = match prev_tile {
-                    WaitingAreaMapTile::Seat { occupied: false }
-                        if occupant_behavior.would_enter_seat(prev_map, idx) =>
-                    {
+                *next_tile = match Self::next_tile(&mut occupant_behavior, prev_map, idx, prev_tile)
+                {
+                    Some(tile) => {
                         changed = true;
-                        WaitingAreaMapTile::Seat { occupied: true }
+                        tile
                     }
-                    WaitingAreaMapTile::Seat { occupied: true }
-                        if occupant_behavior.would_leave_seat(prev_map, idx) =>
-                    {
-                        changed = true;
-                        WaitingAreaMapTile::Seat { occupied: false }
-                    }
-                    _ => prev_tile,
+                    None => prev_tile,
                 };
             });
 
         if changed {
             self.curr_map_idx = next_map_idx;
+            self.steps += 1;
+            Some(self.current_state())
+        } else {
+            None
+        }
+    }
+
+    /// Steps the simulation until a step makes no changes, returning the number of steps that did.
+    ///
+    /// This never returns if `occupant_behavior` makes the map oscillate instead of stabilizing,
+    /// like a [`ThresholdBehavior`] with a `leave_threshold` of 3 does on the puzzle sample.
+    pub fn run_to_stable<B>(&mut self, mut occupant_behavior: B) -> usize
+    where
+        B: WaitingAreaOccupantBehavior,
+    {
+        let mut num_steps = 0;
+        while self.next_step(&mut occupant_behavior).is_some() {
+            num_steps += 1;
+        }
+        num_steps
+    }
+
+    /// Like [`Self::next_step`], but computes tiles in parallel. Each worker thread gets its own
+    /// clone of `occupant_behavior`.
+    pub fn next_step_par<B>(&mut self, occupant_behavior: &B) -> Option<&WaitingAreaMap>
+    where
+        B: WaitingAreaOccupantBehavior + Clone + Send + Sync,
+    {
+        let (prev_map, (next_map_idx, next_map)) = self.split_maps_mut();
+
+        let changed = prev_map
+            .tiles
+            .par_iter()
+            .zip(next_map.tiles.par_iter_mut())
+            .enumerate()
+            .map_init(
+                || occupant_behavior.clone(),
+                |occupant_behavior, (idx, (&prev_tile, next_tile))| match Self::next_tile(
+                    occupant_behavior,
+                    prev_map,
+                    idx,
+                    prev_tile,
+                ) {
+                    Some(tile) => {
+                        *next_tile = tile;
+                        true
+                    }
+                    None => {
+                        *next_tile = prev_tile;
+                        false
+                    }
+                },
+            )
+            .reduce(|| false, |a, b| a || b);
+
+        if changed {
+            self.curr_map_idx = next_map_idx;
+            self.steps += 1;
             Some(self.current_state())
         } else {
             None
@@ -425,72 +1156,297 @@ impl WaitingAreaSeatingSimulation {
         let &Self {
             curr_map_idx,
             ref map_copies,
+            ..
         } = self;
         &map_copies[curr_map_idx]
     }
 }
 
-fn num_seats_with_behavior<B>(mut b: B) -> anyhow::Result<usize>
+#[test]
+fn reset_simulation() {
+    let initial_map = SAMPLE.parse::<WaitingAreaMap>().unwrap();
+    let mut simulation = WaitingAreaSeatingSimulation::new(initial_map.clone());
+    assert_eq!(simulation.step_count(), 0);
+
+    simulation.next_step(Part1OccupantBehavior).unwrap();
+    simulation.next_step(Part1OccupantBehavior).unwrap();
+    simulation.next_step(Part1OccupantBehavior).unwrap();
+    assert_eq!(simulation.step_count(), 3);
+    assert_ne!(simulation.current_state(), &initial_map);
+
+    simulation.reset();
+    assert_eq!(simulation.current_state(), &initial_map);
+    assert_eq!(simulation.step_count(), 0);
+
+    assert_eq!(simulation.run_to_stable(Part1OccupantBehavior), 5);
+    assert_eq!(simulation.step_count(), 5);
+}
+
+/// Runs a simulation on the puzzle input with `b` until it stabilizes, returning the number of
+/// occupied seats.
+pub fn num_seats_with_behavior<B>(b: B) -> anyhow::Result<usize>
 where
     B: WaitingAreaOccupantBehavior,
+{
+    Ok(num_stable_occupied_seats(
+        include_str!("d11.txt").parse()?,
+        b,
+    ))
+}
+
+fn num_stable_occupied_seats<B>(map: WaitingAreaMap, b: B) -> usize
+where
+    B: WaitingAreaOccupantBehavior,
+{
+    let mut simulation = WaitingAreaSeatingSimulation::new(map);
+    simulation.run_to_stable(b);
+    simulation.current_state().counts().occupied
+}
+
+pub fn part_1(s: &str) -> anyhow::Result<usize> {
+    let map = s.parse::<WaitingAreaMap>()?;
+    let behavior = ThresholdBehavior::PART_1.precompute(&map);
+    Ok(num_stable_occupied_seats(map, behavior))
+}
+
+pub fn part_2(s: &str) -> anyhow::Result<usize> {
+    let map = s.parse::<WaitingAreaMap>()?;
+    let behavior = ThresholdBehavior::PART_2.precompute(&map);
+    Ok(num_stable_occupied_seats(map, behavior))
+}
+
+/// Like [`num_seats_with_behavior`], but computes each step in parallel.
+pub fn num_seats_with_behavior_par<B>(b: B) -> anyhow::Result<usize>
+where
+    B: WaitingAreaOccupantBehavior + Clone + Send + Sync,
 {
     let mut simulation = WaitingAreaSeatingSimulation::new(include_str!("d11.txt").parse()?);
-    while simulation.next_step(&mut b).is_some() {}
-    Ok(simulation
-        .current_state()
-        .tiles()
-        .iter()
-        .filter(|tile| matches!(tile, WaitingAreaMapTile::Seat { occupied: true }))
-        .count())
+    while simulation.next_step_par(&b).is_some() {}
+    Ok(simulation.current_state().occupied_count())
 }
 
+/// Runs a simulation starting from `map` until it stabilizes, recording the number of occupied
+/// seats after each step. The last step recorded is the one that made no changes.
+pub fn occupancy_trace<B>(map: WaitingAreaMap, mut behavior: B) -> Vec<usize>
+where
+    B: WaitingAreaOccupantBehavior,
+{
+    let mut simulation = WaitingAreaSeatingSimulation::new(map);
+    let mut trace = Vec::new();
+    loop {
+        let changed = simulation.next_step(&mut behavior).is_some();
+        trace.push(simulation.current_state().occupied_count());
+        if !changed {
+            break trace;
+        }
+    }
+}
+
+#[test]
+fn p1_sample_occupancy_trace() {
+    assert_eq!(
+        occupancy_trace(SAMPLE.parse().unwrap(), Part1OccupantBehavior),
+        [71, 20, 51, 30, 37, 37],
+    );
+}
+
+/// Which seats an occupant pays attention to when deciding whether to sit down or leave.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NeighborMode {
+    /// The eight tiles immediately surrounding a seat.
+    Adjacent,
+    /// The first seat visible in each of the eight directions from a seat.
+    Visible,
+    /// Like [`Self::Adjacent`], but tiles on one edge of the map neighbor those on the opposite
+    /// edge.
+    Wrapping,
+}
+
+impl NeighborMode {
+    fn num_occupied_neighbors(self, map: &WaitingAreaMap, tile_idx: usize) -> usize {
+        match self {
+            Self::Adjacent => map.occupied_adjacent_count(tile_idx),
+            Self::Visible => map.occupied_visible_count(tile_idx),
+            Self::Wrapping => map.occupied_wrapping_adjacent_count(tile_idx),
+        }
+    }
+}
+
+/// Occupants sit down when none of their neighbors are occupied, and leave once at least
+/// `leave_threshold` of them are.
+#[derive(Clone, Debug)]
+pub struct ThresholdBehavior {
+    pub neighbor_mode: NeighborMode,
+    pub leave_threshold: usize,
+}
+
+impl ThresholdBehavior {
+    pub const PART_1: Self = Self {
+        neighbor_mode: NeighborMode::Adjacent,
+        leave_threshold: 4,
+    };
+    pub const PART_2: Self = Self {
+        neighbor_mode: NeighborMode::Visible,
+        leave_threshold: 5,
+    };
+}
+
+impl ThresholdBehavior {
+    fn enters_seat(&self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
+        self.neighbor_mode
+            .num_occupied_neighbors(prev_map, tile_idx)
+            == 0
+    }
+
+    fn leaves_seat(&self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
+        self.neighbor_mode
+            .num_occupied_neighbors(prev_map, tile_idx)
+            >= self.leave_threshold
+    }
+}
+
+impl WaitingAreaOccupantBehavior for ThresholdBehavior {
+    fn would_enter_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
+        self.enters_seat(prev_map, tile_idx)
+    }
+
+    fn would_leave_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
+        self.leaves_seat(prev_map, tile_idx)
+    }
+}
+
+/// Like [`ThresholdBehavior`], but looks neighbors up from lists precomputed for a map's layout
+/// instead of finding them again on every step.
 #[derive(Clone, Debug)]
-struct Part1OccupantBehavior;
+pub struct PrecomputedThresholdBehavior {
+    neighbors: Vec<ArrayVec<[usize; 8]>>,
+    leave_threshold: usize,
+}
+
+impl ThresholdBehavior {
+    /// Finds the neighbors of every tile of `map` up front. The result only gives correct answers
+    /// for maps with the same layout of seats as `map`.
+    pub fn precompute(&self, map: &WaitingAreaMap) -> PrecomputedThresholdBehavior {
+        let &Self {
+            neighbor_mode,
+            leave_threshold,
+        } = self;
+        PrecomputedThresholdBehavior {
+            neighbors: match neighbor_mode {
+                NeighborMode::Adjacent => map.precompute_adjacent_neighbors(),
+                NeighborMode::Visible => map.precompute_visible_neighbors(),
+                NeighborMode::Wrapping => map.precompute_wrapping_adjacent_neighbors(),
+            },
+            leave_threshold,
+        }
+    }
+}
+
+impl PrecomputedThresholdBehavior {
+    fn num_occupied_neighbors(&self, map: &WaitingAreaMap, tile_idx: usize) -> usize {
+        self.neighbors[tile_idx]
+            .iter()
+            .filter(|&&o| map.tiles[o].is_occupied())
+            .count()
+    }
+}
+
+impl WaitingAreaOccupantBehavior for PrecomputedThresholdBehavior {
+    fn would_enter_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
+        self.num_occupied_neighbors(prev_map, tile_idx) == 0
+    }
+
+    fn would_leave_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
+        self.num_occupied_neighbors(prev_map, tile_idx) >= self.leave_threshold
+    }
+}
+
+#[test]
+fn precomputed_neighbors() {
+    let mut simulation = WaitingAreaSeatingSimulation::new(SAMPLE.parse().unwrap());
+    simulation.next_step(Part1OccupantBehavior).unwrap();
+    simulation.next_step(Part1OccupantBehavior).unwrap();
+    let map = simulation.current_state();
+
+    let visible = map.precompute_visible_neighbors();
+    assert_eq!(&visible[0][..], [10, 2, 11]);
+    let adjacent = map.precompute_adjacent_neighbors();
+    assert_eq!(&adjacent[0][..], [1, 10, 11]);
+
+    (0..map.tiles().len()).for_each(|offset| {
+        let occupied = |neighbors: &[usize]| {
+            neighbors
+                .iter()
+                .map(|&o| map.tiles()[o].is_occupied())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            occupied(&visible[offset]),
+            map.get_visible_seats(offset).collect::<Vec<_>>(),
+        );
+        assert_eq!(
+            occupied(&adjacent[offset]),
+            map.get_adjacent_tiles(offset)
+                .map(WaitingAreaMapTile::is_occupied)
+                .collect::<Vec<_>>(),
+        );
+    });
+
+    [
+        (ThresholdBehavior::PART_1, 37),
+        (ThresholdBehavior::PART_2, 26),
+    ]
+    .iter()
+    .for_each(|(behavior, expected)| {
+        let map = SAMPLE.parse::<WaitingAreaMap>().unwrap();
+        let behavior = behavior.precompute(&map);
+        assert_eq!(num_stable_occupied_seats(map, behavior), *expected);
+    });
+}
+
+/// Occupant behavior for part 1. This finds neighbors again on every step; for repeated steps on
+/// one map, [`ThresholdBehavior::precompute`] on [`ThresholdBehavior::PART_1`] is faster.
+#[derive(Clone, Debug)]
+pub struct Part1OccupantBehavior;
 
 impl WaitingAreaOccupantBehavior for Part1OccupantBehavior {
     fn would_enter_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
-        prev_map
-            .get_adjacent_tiles(tile_idx)
-            .all(|tile| !matches!(tile, WaitingAreaMapTile::Seat { occupied: true }))
+        ThresholdBehavior::PART_1.enters_seat(prev_map, tile_idx)
     }
 
     fn would_leave_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
-        prev_map.get_adjacent_tiles(tile_idx).any({
-            let mut count = 0;
-            move |tile| {
-                if matches!(tile, WaitingAreaMapTile::Seat { occupied: true }) {
-                    count += 1;
-                }
-                count == 4
-            }
-        })
+        ThresholdBehavior::PART_1.leaves_seat(prev_map, tile_idx)
     }
 }
 
+#[test]
+fn p1_sample_steps_to_stable() {
+    let mut simulation = WaitingAreaSeatingSimulation::new(SAMPLE.parse().unwrap());
+    assert_eq!(simulation.run_to_stable(Part1OccupantBehavior), 5);
+    assert_eq!(simulation.current_state().occupied_count(), 37);
+}
+
 #[test]
 fn p1_answer() {
     assert_eq!(
         num_seats_with_behavior(Part1OccupantBehavior).unwrap(),
         2386
     );
+    assert_eq!(part_1(include_str!("d11.txt")).unwrap(), 2386);
 }
 
+/// Occupant behavior for part 2. This finds neighbors again on every step; for repeated steps on
+/// one map, [`ThresholdBehavior::precompute`] on [`ThresholdBehavior::PART_2`] is faster.
 #[derive(Clone, Debug)]
-struct Part2OccupantBehavior;
+pub struct Part2OccupantBehavior;
 
 impl WaitingAreaOccupantBehavior for Part2OccupantBehavior {
     fn would_enter_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
-        prev_map
-            .get_visible_seats(tile_idx)
-            .all(|occupied| !occupied)
+        ThresholdBehavior::PART_2.enters_seat(prev_map, tile_idx)
     }
 
     fn would_leave_seat(&mut self, prev_map: &WaitingAreaMap, tile_idx: usize) -> bool {
-        prev_map
-            .get_visible_seats(tile_idx)
-            .filter(|&occupied| occupied)
-            .count()
-            >= 5
+        ThresholdBehavior::PART_2.leaves_seat(prev_map, tile_idx)
     }
 }
 
@@ -533,12 +1489,9 @@ fn p2_sample() {
         .parse::<WaitingAreaMap>()
         .unwrap();
 
-        assert_eq!(
-            map.get_visible_seats(find_top_left_empty_seat(&map).unwrap())
-                .filter(|&occupied| occupied)
-                .count(),
-            8,
-        );
+        let center = find_top_left_empty_seat(&map).unwrap();
+        assert_eq!(map.occupied_visible_count(center), 8);
+        assert_eq!(map.occupied_adjacent_count(center), 2);
     }
 
     {
@@ -555,6 +1508,13 @@ fn p2_sample() {
                 .collect::<Vec<_>>(),
             &[false],
         );
+
+        let mut expected = [None; 8];
+        expected[Direction8::E.index()] = Some(false);
+        assert_eq!(
+            map.visible_seats_by_direction(find_top_left_empty_seat(&map).unwrap()),
+            expected,
+        );
     }
 
     {
@@ -701,4 +1661,61 @@ fn p2_answer() {
         num_seats_with_behavior(Part2OccupantBehavior).unwrap(),
         2091,
     );
+    assert_eq!(part_2(include_str!("d11.txt")).unwrap(), 2091);
+}
+
+#[test]
+fn parallel_answers() {
+    assert_eq!(
+        num_seats_with_behavior_par(Part1OccupantBehavior).unwrap(),
+        2386,
+    );
+    assert_eq!(
+        num_seats_with_behavior_par(Part2OccupantBehavior).unwrap(),
+        2091,
+    );
+}
+
+#[test]
+fn custom_threshold() {
+    let map = WaitingAreaMap::from_tiles(
+        "LLLLLLL.L"
+            .chars()
+            .map(|c| WaitingAreaMapTile::try_from(c).unwrap())
+            .collect(),
+        3,
+    )
+    .unwrap();
+    let run = |behavior| {
+        let mut simulation = WaitingAreaSeatingSimulation::new(map.clone());
+        simulation.run_to_stable(behavior);
+        simulation.current_state().occupied_count()
+    };
+
+    assert_eq!(run(ThresholdBehavior::PART_1), 4);
+    assert_eq!(run(ThresholdBehavior::PART_2), 6);
+    assert_eq!(
+        run(ThresholdBehavior {
+            neighbor_mode: NeighborMode::Adjacent,
+            leave_threshold: 3,
+        }),
+        5,
+    );
+}
+
+#[test]
+fn wrapping_neighbors() {
+    let behavior = ThresholdBehavior {
+        neighbor_mode: NeighborMode::Wrapping,
+        leave_threshold: 4,
+    };
+    let map = SAMPLE.parse::<WaitingAreaMap>().unwrap();
+
+    let mut simulation = WaitingAreaSeatingSimulation::new(map.clone());
+    simulation.run_to_stable(behavior.clone());
+    assert_eq!(simulation.current_state().occupied_count(), 30);
+
+    let mut simulation = WaitingAreaSeatingSimulation::new(map.clone());
+    simulation.run_to_stable(behavior.precompute(&map));
+    assert_eq!(simulation.current_state().occupied_count(), 30);
 }
@@ -1,11 +1,14 @@
 use {
-    crate::parsing::lines_without_endings,
+    crate::{grid::Point, parsing::lines_without_endings},
     anyhow::{anyhow, bail, Context},
-    std::{fmt::Debug, str::FromStr},
+    std::{
+        fmt::{self, Debug, Display, Formatter},
+        str::FromStr,
+    },
     ux::u62,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum NavigationInstruction {
     Move {
         direction: MoveDirection,
@@ -32,13 +35,11 @@ impl FromStr for NavigationInstruction {
                 .with_context(|| anyhow!("unable to parse {:?} as unit for movement", s))
         };
 
-        let parse_degrees = |s| {
-            Ok(match s {
-                "90" => Degrees::Ninety,
-                "180" => Degrees::OneEighty,
-                "270" => Degrees::TwoSeventy,
-                _ => bail!("{:?} is not recognized as a valid turn degrees value"),
-            })
+        let parse_degrees = |s: &str| {
+            s.parse::<u64>()
+                .map_err(anyhow::Error::from)
+                .and_then(|degrees| Degrees::from_degrees(degrees).context("not a multiple of 90"))
+                .with_context(|| anyhow!("{:?} is not recognized as a valid turn degrees value", s))
         };
 
         let action_char = chars.next().context("string is empty")?;
@@ -69,6 +70,47 @@ impl FromStr for NavigationInstruction {
     }
 }
 
+impl Display for NavigationInstruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Move { direction, units } => {
+                let action_char = match direction {
+                    MoveDirection::Cardinal(CardinalDirection::North) => 'N',
+                    MoveDirection::Cardinal(CardinalDirection::East) => 'E',
+                    MoveDirection::Cardinal(CardinalDirection::South) => 'S',
+                    MoveDirection::Cardinal(CardinalDirection::West) => 'W',
+                    MoveDirection::Forward => 'F',
+                    MoveDirection::Backward => 'B',
+                };
+                write!(f, "{}{}", action_char, units)
+            }
+            Self::Turn(TurnInstruction {
+                direction,
+                degrees: Degrees { num_quarter_turns },
+            }) => {
+                let action_char = match direction {
+                    TurnDirection::Left => 'L',
+                    TurnDirection::Right => 'R',
+                };
+                write!(f, "{}{}", action_char, u16::from(*num_quarter_turns) * 90)
+            }
+        }
+    }
+}
+
+#[test]
+fn instruction_display_round_trip() -> anyhow::Result<()> {
+    lines_without_endings(SAMPLE)
+        .chain(["L180", "R270", "B5"].iter().copied())
+        .try_for_each(|line| -> anyhow::Result<()> {
+            let instruction = line.parse::<NavigationInstruction>()?;
+            let displayed = instruction.to_string();
+            assert_eq!(displayed, line);
+            assert_eq!(displayed.parse::<NavigationInstruction>()?, instruction);
+            Ok(())
+        })
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum CardinalDirection {
     North,
@@ -77,30 +119,43 @@ pub enum CardinalDirection {
     West,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MoveDirection {
     Cardinal(CardinalDirection),
     Forward,
     Backward,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TurnInstruction {
     direction: TurnDirection,
     degrees: Degrees,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum TurnDirection {
     Right,
     Left,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum Degrees {
-    Ninety,
-    OneEighty,
-    TwoSeventy,
+/// An amount to turn by, reduced to less than a full turn.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Degrees {
+    /// Always in `0..4`.
+    num_quarter_turns: u8,
+}
+
+impl Degrees {
+    /// Reduces `degrees` to less than a full turn, or returns `None` if it isn't a multiple of 90.
+    pub fn from_degrees(degrees: u64) -> Option<Self> {
+        if degrees % 90 == 0 {
+            Some(Self {
+                num_quarter_turns: ((degrees / 90) % 4) as u8,
+            })
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -109,14 +164,30 @@ pub struct Ship {
     orientation: CardinalDirection,
 }
 
+impl Default for Ship {
+    fn default() -> Self {
+        Self::at((0, 0), CardinalDirection::East)
+    }
+}
+
 impl Ship {
-    fn new() -> Self {
+    /// Creates a ship at the origin, facing east.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a ship at `position` (east and north being positive), facing `orientation`.
+    pub fn at(position: (i64, i64), orientation: CardinalDirection) -> Self {
         Self {
-            position: (0, 0),
-            orientation: CardinalDirection::East,
+            position,
+            orientation,
         }
     }
 
+    pub fn orientation(&self) -> CardinalDirection {
+        self.orientation
+    }
+
     pub fn navigate(&mut self, instruction: NavigationInstruction) -> anyhow::Result<()> {
         let Self {
             position,
@@ -124,14 +195,20 @@ impl Ship {
         } = self;
 
         match instruction {
-            NavigationInstruction::Turn(inst) => *orientation = inst.turn(*orientation).unwrap(),
+            NavigationInstruction::Turn(inst) => {
+                *orientation = inst.turn(*orientation).with_context(|| {
+                    anyhow!("failed to turn from {:?} with {:?}", orientation, inst)
+                })?
+            }
             NavigationInstruction::Move { units, direction } => {
                 *position = translate_pos(
                     *position,
                     units,
                     match direction {
                         MoveDirection::Forward => *orientation,
-                        MoveDirection::Backward => orientation.reverse().unwrap(),
+                        MoveDirection::Backward => orientation.reverse().with_context(|| {
+                            anyhow!("failed to reverse orientation {:?}", orientation)
+                        })?,
                         MoveDirection::Cardinal(dir) => dir,
                     },
                 )?
@@ -161,6 +238,29 @@ pub enum EastWest {
     West,
 }
 
+/// A position in the form returned by [`Ship::position`], displayed like `E17 S8`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CompassPosition(pub ((EastWest, u64), (NorthSouth, u64)));
+
+impl Display for CompassPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let &Self(((east_west, east_west_units), (north_south, north_south_units))) = self;
+        let east_west = match east_west {
+            EastWest::East => 'E',
+            EastWest::West => 'W',
+        };
+        let north_south = match north_south {
+            NorthSouth::North => 'N',
+            NorthSouth::South => 'S',
+        };
+        write!(
+            f,
+            "{}{} {}{}",
+            east_west, east_west_units, north_south, north_south_units
+        )
+    }
+}
+
 #[test]
 fn p1_sample() -> anyhow::Result<()> {
     let ship = navigate(
@@ -198,6 +298,31 @@ fn p1_sample() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn custom_start() -> anyhow::Result<()> {
+    let ship = navigate(
+        Ship::at((5, -5), CardinalDirection::North),
+        parse_navigation_instructions("F3")?,
+        None,
+    )?;
+    assert_eq!(
+        ship.position(),
+        ((EastWest::East, 5), (NorthSouth::South, 2))
+    );
+    assert_eq!(ship.orientation(), CardinalDirection::North);
+
+    let navigation_system = navigate(
+        NavigationSystem::with_waypoint((5, -5), (0, 1)),
+        parse_navigation_instructions("F3")?,
+        None,
+    )?;
+    assert_eq!(
+        navigation_system.position(),
+        ((EastWest::East, 5), (NorthSouth::South, 2))
+    );
+    Ok(())
+}
+
 fn parse_navigation_instructions(s: &str) -> anyhow::Result<Vec<NavigationInstruction>> {
     lines_without_endings(s)
         .enumerate()
@@ -208,8 +333,39 @@ fn parse_navigation_instructions(s: &str) -> anyhow::Result<Vec<NavigationInstru
         .collect()
 }
 
-fn abs_unsigned(x: i64) -> u64 {
-    x.checked_abs().map(|i| i as u64).unwrap_or(x as u64)
+pub fn part_1(s: &str) -> anyhow::Result<u64> {
+    navigate(Ship::new(), parse_navigation_instructions(s)?, None)
+        .map(|ship| ship.manhattan_distance_from_origin())
+}
+
+pub fn part_2(s: &str) -> anyhow::Result<u64> {
+    navigate(
+        NavigationSystem::new(),
+        parse_navigation_instructions(s)?,
+        None,
+    )
+    .map(|navigation_system| navigation_system.manhattan_distance_from_origin())
+}
+
+#[test]
+fn backward_movement() -> anyhow::Result<()> {
+    let ship = navigate(Ship::new(), parse_navigation_instructions("B5")?, None)?;
+    assert_eq!(
+        ship.position(),
+        ((EastWest::West, 5), (NorthSouth::North, 0))
+    );
+    assert_eq!(ship.orientation(), CardinalDirection::East);
+
+    let ship = navigate(
+        Ship::at((2, 0), CardinalDirection::North),
+        parse_navigation_instructions("B3\nF1")?,
+        None,
+    )?;
+    assert_eq!(
+        ship.position(),
+        ((EastWest::East, 2), (NorthSouth::South, 2))
+    );
+    Ok(())
 }
 
 #[test]
@@ -297,12 +453,36 @@ fn p2_sample() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn compass_positions() -> anyhow::Result<()> {
+    let ship = navigate(Ship::new(), parse_navigation_instructions(SAMPLE)?, None)?;
+    assert_eq!(CompassPosition(ship.position()).to_string(), "E17 S8");
+
+    let navigation_system = navigate(
+        NavigationSystem::new(),
+        parse_navigation_instructions(SAMPLE)?,
+        None,
+    )?;
+    assert_eq!(
+        CompassPosition(navigation_system.waypoint()).to_string(),
+        "E4 S10"
+    );
+    assert_eq!(navigation_system.waypoint_manhattan_distance(), 14);
+    Ok(())
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct NavigationSystem {
     ship_position: (i64, i64),
     waypoint: (i64, i64),
 }
 
+impl Default for NavigationSystem {
+    fn default() -> Self {
+        Self::with_waypoint((0, 0), (10, 1))
+    }
+}
+
 impl NavigationSystem {
     fn navigate(&mut self, instruction: NavigationInstruction) -> anyhow::Result<()> {
         let Self {
@@ -355,10 +535,18 @@ impl NavigationSystem {
         Ok(())
     }
 
-    fn new() -> Self {
+    /// Creates a navigation system with the ship at the origin and the waypoint 10 units east and
+    /// 1 unit north of it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a navigation system with the ship at `ship` and a waypoint at `waypoint`, relative
+    /// to the ship. East and north are positive.
+    pub fn with_waypoint(ship: (i64, i64), waypoint: (i64, i64)) -> Self {
         Self {
-            ship_position: (0, 0),
-            waypoint: (10, 1),
+            ship_position: ship,
+            waypoint,
         }
     }
 
@@ -373,6 +561,11 @@ impl NavigationSystem {
     pub fn manhattan_distance_from_origin(&self) -> u64 {
         manhattan_distance(self.ship_position)
     }
+
+    /// The Manhattan distance of the waypoint from the ship.
+    pub fn waypoint_manhattan_distance(&self) -> u64 {
+        manhattan_distance(self.waypoint)
+    }
 }
 
 fn translate_pos(
@@ -426,6 +619,120 @@ impl Navigate for NavigationSystem {
     }
 }
 
+/// Navigates by calling the wrapped closure with each instruction.
+///
+/// A blanket implementation of [`Navigate`] for closures would overlap with the one for `&mut T`,
+/// since `&mut F` is itself a closure whenever `F` is.
+pub struct NavigateFn<F>(pub F);
+
+impl<F> Navigate for NavigateFn<F>
+where
+    F: FnMut(NavigationInstruction) -> anyhow::Result<()>,
+{
+    fn navigate(&mut self, instruction: NavigationInstruction) -> anyhow::Result<()> {
+        (self.0)(instruction)
+    }
+}
+
+#[test]
+fn navigate_with_closure() -> anyhow::Result<()> {
+    let mut recorded = Vec::new();
+    let mut navigatable = NavigateFn(|instruction| {
+        recorded.push(instruction);
+        Ok(())
+    });
+    parse_navigation_instructions(SAMPLE)?
+        .into_iter()
+        .try_for_each(|instruction| navigatable.navigate(instruction))?;
+    assert_eq!(recorded, parse_navigation_instructions(SAMPLE)?);
+
+    let mut failing = NavigateFn(|_instruction| Err(anyhow!("nope")));
+    assert!(failing
+        .navigate(parse_navigation_instructions(SAMPLE)?.remove(0))
+        .is_err());
+    Ok(())
+}
+
+/// Something with a ship position that changes as it navigates.
+pub trait Positioned {
+    /// The ship's current position, with east and north being positive.
+    fn ship_position(&self) -> (i64, i64);
+}
+
+impl Positioned for Ship {
+    fn ship_position(&self) -> (i64, i64) {
+        self.position
+    }
+}
+
+impl Positioned for NavigationSystem {
+    fn ship_position(&self) -> (i64, i64) {
+        self.ship_position
+    }
+}
+
+/// Wraps a [`Navigate`] implementor, recording the ship's position after each instruction, even
+/// if the instruction didn't move the ship.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PathRecorder<T> {
+    navigatable: T,
+    path: Vec<(i64, i64)>,
+}
+
+impl<T> PathRecorder<T> {
+    pub fn new(navigatable: T) -> Self {
+        Self {
+            navigatable,
+            path: Vec::new(),
+        }
+    }
+
+    /// Positions the ship has been in, one for each instruction navigated so far.
+    pub fn path(&self) -> &[(i64, i64)] {
+        &self.path
+    }
+
+    pub fn into_inner(self) -> T {
+        self.navigatable
+    }
+}
+
+impl<T> Navigate for PathRecorder<T>
+where
+    T: Navigate + Positioned,
+{
+    fn navigate(&mut self, instruction: NavigationInstruction) -> anyhow::Result<()> {
+        let Self { navigatable, path } = self;
+        navigatable.navigate(instruction)?;
+        path.push(navigatable.ship_position());
+        Ok(())
+    }
+}
+
+#[test]
+fn path_recording() -> anyhow::Result<()> {
+    let recorder = navigate(
+        PathRecorder::new(Ship::new()),
+        parse_navigation_instructions(SAMPLE)?,
+        None,
+    )?;
+    assert_eq!(
+        recorder.path(),
+        [(10, 0), (10, 3), (17, 3), (17, 3), (17, -8)],
+    );
+
+    let recorder = navigate(
+        PathRecorder::new(NavigationSystem::new()),
+        parse_navigation_instructions(SAMPLE)?,
+        None,
+    )?;
+    assert_eq!(
+        recorder.path(),
+        [(100, 10), (100, 10), (170, 38), (170, 38), (214, -72)],
+    );
+    Ok(())
+}
+
 const SAMPLE: &str = "\
 F10
 N3
@@ -472,20 +779,43 @@ impl Turn for CardinalDirection {
     }
 }
 
+impl CardinalDirection {
+    /// Turns by `degrees` towards `direction`, or returns `None` if `degrees` isn't a multiple of
+    /// 90.
+    pub fn turn_by_degrees(self, direction: TurnDirection, degrees: u16) -> Option<Self> {
+        TurnInstruction {
+            direction,
+            degrees: Degrees::from_degrees(u64::from(degrees))?,
+        }
+        .turn(self)
+    }
+}
+
+#[test]
+fn turn_by_degrees() {
+    use CardinalDirection::*;
+
+    assert_eq!(North.turn_by_degrees(TurnDirection::Right, 270), Some(West));
+    assert_eq!(North.turn_by_degrees(TurnDirection::Left, 270), Some(East));
+    assert_eq!(South.turn_by_degrees(TurnDirection::Left, 0), Some(South));
+    assert_eq!(East.turn_by_degrees(TurnDirection::Right, 450), Some(South));
+    assert_eq!(East.turn_by_degrees(TurnDirection::Right, 45), None);
+}
+
 impl TurnInstruction {
     fn turn<T>(&self, t: T) -> Option<T>
     where
         T: Turn,
     {
-        let TurnInstruction { direction, degrees } = self;
-
-        match (degrees, direction) {
-            (Degrees::Ninety, TurnDirection::Left)
-            | (Degrees::TwoSeventy, TurnDirection::Right) => t.single_turn_left(),
-            (Degrees::Ninety, TurnDirection::Right)
-            | (Degrees::TwoSeventy, TurnDirection::Left) => t.single_turn_right(),
-            (Degrees::OneEighty, _) => t.reverse(),
-        }
+        let &TurnInstruction {
+            direction,
+            degrees: Degrees { num_quarter_turns },
+        } = self;
+
+        (0..num_quarter_turns).try_fold(t, |t, _| match direction {
+            TurnDirection::Left => t.single_turn_left(),
+            TurnDirection::Right => t.single_turn_right(),
+        })
     }
 }
 
@@ -506,6 +836,28 @@ impl Turn for (i64, i64) {
     }
 }
 
+#[test]
+fn arbitrary_turns() -> anyhow::Result<()> {
+    let ship = navigate(Ship::new(), parse_navigation_instructions("L360")?, None)?;
+    assert_eq!(ship.orientation(), CardinalDirection::East);
+
+    let ship = navigate(Ship::new(), parse_navigation_instructions("R450")?, None)?;
+    assert_eq!(ship.orientation(), CardinalDirection::South);
+
+    assert!("R45".parse::<NavigationInstruction>().is_err());
+
+    let navigation_system = navigate(
+        NavigationSystem::with_waypoint((0, 0), (10, 4)),
+        parse_navigation_instructions("R270")?,
+        None,
+    )?;
+    assert_eq!(
+        navigation_system.waypoint(),
+        ((EastWest::West, 4), (NorthSouth::North, 10)),
+    );
+    Ok(())
+}
+
 #[test]
 fn p2_answer() -> anyhow::Result<()> {
     let navigation_system = navigate(
@@ -528,7 +880,8 @@ fn p2_answer() -> anyhow::Result<()> {
 const INPUT: &str = include_str!("d12.txt");
 
 fn convert_position(coords: (i64, i64)) -> ((EastWest, u64), (NorthSouth, u64)) {
-    let (x, y) = coords;
+    let point = Point::from(coords);
+    let Point { x, y } = point;
     (
         (
             if x.is_negative() {
@@ -536,7 +889,7 @@ fn convert_position(coords: (i64, i64)) -> ((EastWest, u64), (NorthSouth, u64))
             } else {
                 EastWest::East
             },
-            abs_unsigned(x),
+            point.manhattan_distance(Point { x: 0, y }),
         ),
         (
             if y.is_negative() {
@@ -544,11 +897,11 @@ fn convert_position(coords: (i64, i64)) -> ((EastWest, u64), (NorthSouth, u64))
             } else {
                 NorthSouth::North
             },
-            abs_unsigned(y),
+            point.manhattan_distance(Point { x, y: 0 }),
         ),
     )
 }
 
-fn manhattan_distance((x, y): (i64, i64)) -> u64 {
-    abs_unsigned(x) + abs_unsigned(y)
+fn manhattan_distance(coords: (i64, i64)) -> u64 {
+    Point::ORIGIN.manhattan_distance(coords.into())
 }
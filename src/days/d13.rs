@@ -1,8 +1,9 @@
 use {
-    crate::parsing::lines_without_endings,
-    anyhow::{anyhow, ensure, Context},
+    crate::{math::checked_lcm, parsing::lines_without_endings, Solution},
+    anyhow::{anyhow, Context},
     itertools::Itertools,
-    std::str::FromStr,
+    serde::Serialize,
+    std::{cmp::Reverse, collections::BinaryHeap, iter, num::ParseIntError, str::FromStr},
 };
 
 #[test]
@@ -45,7 +46,19 @@ fn p1_answer() -> anyhow::Result<()> {
     Ok(())
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[test]
+fn p1_answer_from_file() -> anyhow::Result<()> {
+    let calc = Part1Calculation::new(&crate::days::load_input(13)?.parse()?);
+    assert_eq!(calc.answer()?, 3035);
+    assert_eq!(crate::days::solve_from_file(13, 1)?, "3035");
+    Ok(())
+}
+
+pub fn part_1(s: &str) -> anyhow::Result<u32> {
+    Part1Calculation::new(&s.parse()?).answer()
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
 pub struct Part1Calculation {
     soonest_bus: u32,
     wait_after_initial: u32,
@@ -55,11 +68,11 @@ impl Part1Calculation {
     pub fn new(data: &Part1Data) -> Self {
         let Part1Data {
             initial_wait,
-            bus_ids,
+            bus_slots,
         } = data;
-        let (soonest_bus, wait_after_initial) = bus_ids
+        let (soonest_bus, wait_after_initial) = bus_slots
             .iter()
-            .copied()
+            .filter_map(BusSlot::id)
             .map(|bus_id| (bus_id, bus_id - (initial_wait % bus_id)))
             .min_by_key(|(_bus_id, wait)| *wait)
             .unwrap();
@@ -81,42 +94,352 @@ impl Part1Calculation {
     }
 }
 
+/// Yields every departure at or after `after` of the buses in service in `data`, as `(bus_id,
+/// timestamp)` pairs in ascending order of timestamp. Departures at the same timestamp are
+/// ordered by bus ID.
+pub fn next_departures(data: &Part1Data, after: u32) -> impl Iterator<Item = (u32, u32)> {
+    let mut upcoming = data
+        .bus_slots
+        .iter()
+        .filter_map(BusSlot::id)
+        .filter_map(|bus_id| {
+            let wait = (bus_id - after % bus_id) % bus_id;
+            after
+                .checked_add(wait)
+                .map(|timestamp| Reverse((timestamp, bus_id)))
+        })
+        .collect::<BinaryHeap<_>>();
+
+    iter::from_fn(move || {
+        let Reverse((timestamp, bus_id)) = upcoming.pop()?;
+        if let Some(next_timestamp) = timestamp.checked_add(bus_id) {
+            upcoming.push(Reverse((next_timestamp, bus_id)));
+        }
+        Some((bus_id, timestamp))
+    })
+}
+
+#[test]
+fn sample_next_departures() -> anyhow::Result<()> {
+    let data = "939\n7,13,x,x,59,x,31,19\n".parse::<Part1Data>()?;
+    assert_eq!(
+        next_departures(&data, 939).take(4).collect::<Vec<_>>(),
+        [(59, 944), (7, 945), (13, 949), (19, 950)],
+    );
+    assert_eq!(next_departures(&data, 945).next(), Some((7, 945)));
+    Ok(())
+}
+
+/// An entry in a bus schedule.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BusSlot {
+    /// A bus ID, which [`parse_bus_slots`] guarantees is non-zero.
+    Id(u32),
+    /// Marked with `x` in a schedule.
+    OutOfService,
+}
+
+impl BusSlot {
+    const OUT_OF_SERVICE: &'static str = "x";
+
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            &Self::Id(id) => Some(id),
+            Self::OutOfService => None,
+        }
+    }
+}
+
+/// A failure to parse puzzle input for day 13.
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleParseError {
+    #[error("expected two lines of input")]
+    ExpectedTwoLines,
+    #[error("failed to parse initial wait")]
+    BadInitialWait(#[source] ParseIntError),
+    #[error("failed to parse bus ID at index {index}")]
+    BadBusId { index: usize, source: ParseIntError },
+    #[error("bus ID at index {index} is 0")]
+    ZeroBusId { index: usize },
+    #[error("schedule line is empty")]
+    EmptySchedule,
+    #[error("schedule contains no in-service buses")]
+    NoBuses,
+}
+
+/// Parses a comma-separated schedule line, i.e., the second line of puzzle input.
+pub fn parse_bus_slots(s: &str) -> Result<Vec<BusSlot>, ScheduleParseError> {
+    if s.is_empty() {
+        return Err(ScheduleParseError::EmptySchedule);
+    }
+
+    let bus_slots = s
+        .split(',')
+        .enumerate()
+        .map(|(index, raw_slot)| {
+            if raw_slot == BusSlot::OUT_OF_SERVICE {
+                return Ok(BusSlot::OutOfService);
+            }
+            match raw_slot.parse::<u32>() {
+                Ok(0) => Err(ScheduleParseError::ZeroBusId { index }),
+                Ok(id) => Ok(BusSlot::Id(id)),
+                Err(source) => Err(ScheduleParseError::BadBusId { index, source }),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if bus_slots.iter().all(|slot| slot.id().is_none()) {
+        return Err(ScheduleParseError::NoBuses);
+    }
+
+    Ok(bus_slots)
+}
+
+#[test]
+fn bus_slots() -> anyhow::Result<()> {
+    let bus_slots = parse_bus_slots("7,13,x,x,59,x,31,19")?;
+    assert_eq!(
+        bus_slots
+            .iter()
+            .positions(|&slot| slot == BusSlot::OutOfService)
+            .collect::<Vec<_>>(),
+        [2, 3, 5],
+    );
+    assert_eq!(
+        bus_slots.iter().filter_map(BusSlot::id).collect::<Vec<_>>(),
+        [7, 13, 59, 31, 19],
+    );
+    Ok(())
+}
+
+#[test]
+fn malformed_schedules() {
+    let error = |s: &str| s.parse::<Part1Data>().unwrap_err();
+
+    assert!(matches!(
+        error("939\n"),
+        ScheduleParseError::ExpectedTwoLines
+    ));
+    assert!(matches!(
+        error("939\n7,13\n19\n"),
+        ScheduleParseError::ExpectedTwoLines
+    ));
+    assert!(matches!(
+        error("soon\n7,13\n"),
+        ScheduleParseError::BadInitialWait(_)
+    ));
+    assert!(matches!(
+        error("939\n7,x,thirteen\n"),
+        ScheduleParseError::BadBusId { index: 2, .. }
+    ));
+    assert!(matches!(
+        error("939\n7,x,0\n"),
+        ScheduleParseError::ZeroBusId { index: 2 }
+    ));
+    assert!(matches!(
+        error("939\n\n"),
+        ScheduleParseError::EmptySchedule
+    ));
+    assert!(matches!(error("939\nx,x,x\n"), ScheduleParseError::NoBuses));
+    assert!(crate::days::solve(13, 1, "939\n0").is_err());
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct Part1Data {
     initial_wait: u32,
-    bus_ids: Vec<u32>,
+    bus_slots: Vec<BusSlot>,
 }
 
 impl FromStr for Part1Data {
-    type Err = anyhow::Error;
+    type Err = ScheduleParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (initial_wait, bus_ids) = {
-            let (raw_initial_wait, raw_bus_ids) = lines_without_endings(s)
-                .collect_tuple()
-                .context("expected two lines of input")?;
-            (
-                raw_initial_wait
-                    .parse::<u32>()
-                    .with_context(|| anyhow!("failed to parse {:?} initial wait"))?,
-                raw_bus_ids
-                    .split(',')
-                    .filter(|&s| s != "x")
-                    .enumerate()
-                    .map(|(raw_id_idx, raw_id)| {
-                        raw_id.parse::<u32>().with_context(|| {
-                            anyhow!("failed to parse raw bus ID {} ({:?})", raw_id_idx, raw_id)
-                        })
-                    })
-                    .collect::<anyhow::Result<Vec<_>>>()?,
-            )
-        };
-
-        ensure!(!bus_ids.is_empty(), "no bus IDs specified");
+        let (raw_initial_wait, raw_bus_slots) = lines_without_endings(s)
+            .collect_tuple()
+            .ok_or(ScheduleParseError::ExpectedTwoLines)?;
 
         Ok(Self {
-            initial_wait,
-            bus_ids,
+            initial_wait: raw_initial_wait
+                .parse::<u32>()
+                .map_err(ScheduleParseError::BadInitialWait)?,
+            bus_slots: parse_bus_slots(raw_bus_slots)?,
+        })
+    }
+}
+
+/// Bus IDs paired with the offset from the earliest timestamp at which each must depart.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Part2Data {
+    buses: Vec<(u32, usize)>,
+}
+
+impl Part2Data {
+    /// Parses only the comma-separated schedule line, i.e., the second line of puzzle input.
+    pub fn from_schedule(s: &str) -> Result<Self, ScheduleParseError> {
+        parse_bus_slots(s).map(|bus_slots| Self::from_bus_slots(&bus_slots))
+    }
+
+    pub fn from_bus_slots(bus_slots: &[BusSlot]) -> Self {
+        Self {
+            buses: bus_slots
+                .iter()
+                .enumerate()
+                .filter_map(|(offset, slot)| slot.id().map(|id| (id, offset)))
+                .collect(),
+        }
+    }
+}
+
+impl From<&Part1Data> for Part2Data {
+    fn from(data: &Part1Data) -> Self {
+        Self::from_bus_slots(&data.bus_slots)
+    }
+}
+
+impl FromStr for Part2Data {
+    type Err = ScheduleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Part1Data>().map(|data| Self::from(&data))
+    }
+}
+
+/// Finds the earliest timestamp at which each bus departs at its offset from that timestamp.
+///
+/// This sieves for each bus in turn: once a timestamp satisfies every bus considered so far,
+/// stepping by the least common multiple of their IDs preserves that, so only multiples of that
+/// step need checking for the next bus.
+pub fn part_2(data: &Part2Data) -> anyhow::Result<u128> {
+    let mut timestamp = 0u128;
+    let mut step = 1u128;
+    data.buses.iter().try_for_each(|&(id, offset)| {
+        (|| -> anyhow::Result<()> {
+            let id = u128::from(id);
+            let offset = offset as u128;
+            for _ in 0..id {
+                if (timestamp + offset) % id == 0 {
+                    step = checked_lcm(step, id).context("step is unrepresentable with `u128`")?;
+                    return Ok(());
+                }
+                timestamp = timestamp
+                    .checked_add(step)
+                    .context("timestamp is unrepresentable with `u128`")?;
+            }
+            Err(anyhow!(
+                "no timestamp satisfies this bus along with the ones before it"
+            ))
+        })()
+        .with_context(|| anyhow!("failed to fit bus {} at offset {}", id, offset))
+    })?;
+    Ok(timestamp)
+}
+
+/// Renders, one line per bus, whether the bus departs at its offset from timestamp `t`, so that a
+/// candidate answer for part 2 can be checked by hand.
+pub fn explain_part_2(data: &Part2Data, t: u128) -> String {
+    data.buses
+        .iter()
+        .map(|&(id, offset)| {
+            let departure = t.checked_add(offset as u128);
+            let remainder = departure.and_then(|departure| departure.checked_rem(u128::from(id)));
+            let verdict = if remainder == Some(0) {
+                "satisfied"
+            } else {
+                "unsatisfied"
+            };
+            match (departure, remainder) {
+                (Some(departure), Some(remainder)) => format!(
+                    "bus {} at offset {}: {} % {} == {} ({})\n",
+                    id, offset, departure, id, remainder, verdict,
+                ),
+                (Some(departure), None) => format!(
+                    "bus {} at offset {}: {} % {} is undefined ({})\n",
+                    id, offset, departure, id, verdict,
+                ),
+                (None, _) => format!(
+                    "bus {} at offset {}: {} + {} is unrepresentable with `u128` ({})\n",
+                    id, offset, t, offset, verdict,
+                ),
+            }
         })
+        .collect()
+}
+
+#[test]
+fn p2_sample_explanation() -> anyhow::Result<()> {
+    let data = Part2Data::from_schedule("7,13,x,x,59,x,31,19")?;
+
+    let explanation = explain_part_2(&data, 1068781);
+    assert_eq!(
+        explanation,
+        "\
+bus 7 at offset 0: 1068781 % 7 == 0 (satisfied)
+bus 13 at offset 1: 1068782 % 13 == 0 (satisfied)
+bus 59 at offset 4: 1068785 % 59 == 0 (satisfied)
+bus 31 at offset 6: 1068787 % 31 == 0 (satisfied)
+bus 19 at offset 7: 1068788 % 19 == 0 (satisfied)
+",
+    );
+
+    assert!(explain_part_2(&data, 1068782).contains("(unsatisfied)"));
+    Ok(())
+}
+
+#[test]
+fn p2_samples() -> anyhow::Result<()> {
+    [
+        ("7,13,x,x,59,x,31,19", 1068781),
+        ("17,x,13,19", 3417),
+        ("67,7,59,61", 754018),
+        ("67,x,7,59,61", 779210),
+        ("67,7,x,59,61", 1261476),
+        ("1789,37,47,1889", 1202161486),
+    ]
+    .iter()
+    .try_for_each(|&(schedule, expected)| {
+        assert_eq!(part_2(&Part2Data::from_schedule(schedule)?)?, expected);
+        Ok(())
+    })
+}
+
+#[test]
+fn p2_answer() -> anyhow::Result<()> {
+    let data = include_str!("d13.txt")
+        .parse::<Part2Data>()
+        .context("failed to parse input data")?;
+    assert_eq!(part_2(&data)?, 725169163285238);
+    Ok(())
+}
+
+/// Solves both parts of puzzle input `s`, parsing it only once.
+pub fn solve(s: &str) -> anyhow::Result<(u32, u128)> {
+    let data = s.parse::<Part1Data>()?;
+    Ok((
+        Part1Calculation::new(&data).answer()?,
+        part_2(&Part2Data::from(&data))?,
+    ))
+}
+
+#[test]
+fn solve_sample() -> anyhow::Result<()> {
+    assert_eq!(solve("939\n7,13,x,x,59,x,31,19\n")?, (295, 1068781));
+    Ok(())
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Parsed = Part1Data;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Parsed> {
+        Ok(input.parse()?)
+    }
+
+    fn part_1(data: &Self::Parsed) -> anyhow::Result<String> {
+        Part1Calculation::new(data).answer().map(|a| a.to_string())
+    }
+
+    fn part_2(data: &Self::Parsed) -> anyhow::Result<String> {
+        part_2(&Part2Data::from(data)).map(|t| t.to_string())
     }
 }
@@ -1,5 +1,233 @@
+/// A day's puzzle, split into parsing its input and solving each of its parts.
+pub trait Solution {
+    type Parsed;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Parsed>;
+    fn part_1(parsed: &Self::Parsed) -> anyhow::Result<String>;
+    fn part_2(parsed: &Self::Parsed) -> anyhow::Result<String>;
+}
+
 pub mod days {
-    automod::dir!("src/days/");
+    automod::dir!(pub "src/days/");
+
+    use {
+        crate::Solution,
+        anyhow::{anyhow, bail, Context},
+        std::{fs, path::PathBuf},
+    };
+
+    fn solve_with<S>(part: u8, input: &str) -> anyhow::Result<String>
+    where
+        S: Solution,
+    {
+        let parsed = S::parse(input)?;
+        match part {
+            1 => S::part_1(&parsed),
+            2 => S::part_2(&parsed),
+            _ => bail!("unknown part {}", part),
+        }
+    }
+
+    /// Runs the solver for `part` of `day` against `input`, returning the answer as it would be
+    /// submitted.
+    pub fn solve(day: u8, part: u8, input: &str) -> anyhow::Result<String> {
+        fn answer<T>(res: anyhow::Result<T>) -> anyhow::Result<String>
+        where
+            T: ToString,
+        {
+            res.map(|answer| answer.to_string())
+        }
+
+        match (day, part) {
+            (1, 1..=2) => solve_with::<d01::Day01>(part, input),
+            (2, 1) => answer(d02::part_1_strict(input)),
+            (2, 2) => answer(d02::part_2_strict(input)),
+            (3, 1) => answer(d03::part_1(input)),
+            (3, 2) => answer(d03::part_2(input)),
+            (4, 1) => answer(d04::part_1(input)),
+            (4, 2) => answer(d04::part_2(input)),
+            (5, 1) => answer(d05::part_1(input).map(|d05::SeatId(id)| u16::from(id))),
+            (5, 2) => answer(d05::part_2(input).map(|d05::SeatId(id)| u16::from(id))),
+            (6, 1) => Ok(d06::sum_of_unique_question_answer_counts(input).to_string()),
+            (6, 2) => {
+                Ok(d06::sum_of_group_individuals_who_answered_yes_in_each_group(input).to_string())
+            }
+            (7, 1) => answer(d07::part_1(input)),
+            (7, 2) => answer(d07::part_2(input)),
+            (8, 1) => answer(d08::part_1(input)),
+            (8, 2) => answer(d08::part_2(input)),
+            (9, 1..=2) => solve_with::<d09::Day09>(part, input),
+            (10, 1..=2) => solve_with::<d10::Day10>(part, input),
+            (11, 1) => answer(d11::part_1(input)),
+            (11, 2) => answer(d11::part_2(input)),
+            (12, 1) => answer(d12::part_1(input)),
+            (12, 2) => answer(d12::part_2(input)),
+            (13, 1..=2) => solve_with::<d13::Day13>(part, input),
+            (1..=13, _) => bail!("unknown part {} for day {}", part, day),
+            _ => bail!("unknown day {}", day),
+        }
+    }
+
+    /// Reads the puzzle input for `day` from disk, so that it can be swapped out without
+    /// recompiling.
+    pub fn load_input(day: u8) -> anyhow::Result<String> {
+        let path = [env!("CARGO_MANIFEST_DIR"), "src", "days"]
+            .iter()
+            .collect::<PathBuf>()
+            .join(format!("d{:02}.txt", day));
+        fs::read_to_string(&path)
+            .with_context(|| anyhow!("failed to read input for day {} from {:?}", day, path))
+    }
+
+    /// Like [`solve`], but with the input read by [`load_input`].
+    pub fn solve_from_file(day: u8, part: u8) -> anyhow::Result<String> {
+        solve(day, part, &load_input(day)?)
+    }
+
+    /// Like [`solve`], but returns the intermediate values that lead to an answer where a day
+    /// tracks them, rather than only the answer.
+    pub fn solve_json(day: u8, part: u8, input: &str) -> anyhow::Result<serde_json::Value> {
+        Ok(match (day, part) {
+            (1, 1) => serde_json::to_value(d01::part_1(input)?)?,
+            (1, 2) => serde_json::to_value(d01::part_2(input)?)?,
+            (10, 1) => serde_json::to_value(
+                input
+                    .parse::<d10::JoltageAdapterSet>()?
+                    .connectable()
+                    .diff_counts()?,
+            )?,
+            (13, 1) => serde_json::to_value(d13::Part1Calculation::new(&input.parse()?))?,
+            _ => serde_json::Value::String(solve(day, part, input)?),
+        })
+    }
+
+    #[test]
+    fn unknown_day_or_part() {
+        assert_eq!(solve(99, 1, "").unwrap_err().to_string(), "unknown day 99");
+        assert_eq!(
+            solve(1, 3, "").unwrap_err().to_string(),
+            "unknown part 3 for day 1"
+        );
+    }
+}
+
+pub mod math {
+    use {itertools::Itertools, num_traits::CheckedAdd};
+
+    /// Finds the first `k` distinct positions in `items` (in lexicographic order) whose values add
+    /// up to `target`, returning them in ascending order. Sums that overflow `T` never match, and
+    /// no subset of size 0 is considered.
+    pub fn subset_of_size_summing_to<T>(items: &[T], k: usize, target: T) -> Option<Vec<usize>>
+    where
+        T: Copy + Ord + CheckedAdd + Eq,
+    {
+        match k {
+            0 => return None,
+            2 => return pair_summing_to(items, target).map(|(i, j)| vec![i, j]),
+            _ => (),
+        }
+        (0..items.len()).combinations(k).find(|indices| {
+            let mut values = indices.iter().map(|&idx| items[idx]);
+            let first = values.next().unwrap();
+            values.try_fold(first, |sum, value| sum.checked_add(&value)) == Some(target)
+        })
+    }
+
+    /// Like [`subset_of_size_summing_to`] with a `k` of 2, but without allocating.
+    pub fn pair_summing_to<T>(items: &[T], target: T) -> Option<(usize, usize)>
+    where
+        T: Copy + CheckedAdd + Eq,
+    {
+        items.iter().enumerate().find_map(|(i, a)| {
+            items[i + 1..]
+                .iter()
+                .position(|b| a.checked_add(b) == Some(target))
+                .map(|j| (i, i + 1 + j))
+        })
+    }
+
+    /// The greatest common divisor of `a` and `b`, where `gcd(0, 0)` is `0`.
+    pub fn gcd(mut a: u128, mut b: u128) -> u128 {
+        while b != 0 {
+            let r = a % b;
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    /// The least common multiple of `a` and `b`, where the `lcm` of `0` and anything is `0`.
+    ///
+    /// Panics if the result is unrepresentable with `u128`; see [`checked_lcm`].
+    pub fn lcm(a: u128, b: u128) -> u128 {
+        checked_lcm(a, b).expect("least common multiple is unrepresentable with `u128`")
+    }
+
+    /// Like [`lcm`], but returns `None` if the result is unrepresentable with `u128`.
+    pub fn checked_lcm(a: u128, b: u128) -> Option<u128> {
+        if a == 0 || b == 0 {
+            return Some(0);
+        }
+        (a / gcd(a, b)).checked_mul(b)
+    }
+
+    /// Finds `x` in `0..m` such that `a * x` is congruent to `1` modulo `m`, using the extended
+    /// Euclidean algorithm. There is no such `x` unless `a` and `m` are coprime and `m > 1`.
+    pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+        if m <= 1 {
+            return None;
+        }
+        let (mut old_r, mut r) = (a.rem_euclid(m), m);
+        let (mut old_s, mut s) = (1i128, 0i128);
+        while r != 0 {
+            let quotient = old_r / r;
+            let next_r = old_r - quotient * r;
+            old_r = r;
+            r = next_r;
+            let next_s = old_s - quotient * s;
+            old_s = s;
+            s = next_s;
+        }
+        if old_r == 1 {
+            Some(old_s.rem_euclid(m))
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn gcd_lcm_and_mod_inverse() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(0, 6), 0);
+        assert_eq!(checked_lcm(u128::MAX, u128::MAX - 1), None);
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(-4, 7), Some(5));
+        assert_eq!(mod_inverse(2, 4), None);
+        assert_eq!(mod_inverse(3, 1), None);
+    }
+
+    #[test]
+    fn subsets_summing_to() {
+        let items = [1721u32, 979, 366, 299, 675, 1456];
+        assert_eq!(subset_of_size_summing_to(&items, 2, 2020), Some(vec![0, 3]));
+        assert_eq!(
+            subset_of_size_summing_to(&items, 3, 2020),
+            Some(vec![1, 2, 4])
+        );
+        assert_eq!(subset_of_size_summing_to(&items, 2, 1), None);
+        assert_eq!(subset_of_size_summing_to(&items, 0, 0), None);
+        assert_eq!(subset_of_size_summing_to(&items, 7, 0), None);
+        assert_eq!(subset_of_size_summing_to(&[u8::MAX, 1, 0], 2, 0), None);
+        assert_eq!(
+            subset_of_size_summing_to(&[-3i8, 5, 4], 2, 1),
+            Some(vec![0, 2])
+        );
+        assert_eq!(pair_summing_to(&items, 2020), Some((0, 3)));
+        assert_eq!(pair_summing_to(&[1u8, 1, 1], 2), Some((0, 1)));
+        assert_eq!(pair_summing_to(&[1u8], 2), None);
+    }
 }
 
 pub mod parsing {
@@ -10,4 +238,119 @@ pub mod parsing {
                 .unwrap_or(l)
         })
     }
+
+    /// Splits `s` into groups of lines separated by one or more blank lines. Each group is
+    /// yielded without its final line ending.
+    pub fn blank_line_separated_groups(s: &str) -> impl Iterator<Item = &str> {
+        let mut rest = s;
+        std::iter::from_fn(move || {
+            rest = rest.trim_start_matches(|c| c == '\r' || c == '\n');
+            if rest.is_empty() {
+                return None;
+            }
+
+            let mut line_start = 0;
+            let group_end = loop {
+                let line_end = rest[line_start..]
+                    .find('\n')
+                    .map_or(rest.len(), |idx| line_start + idx);
+                let line = &rest[line_start..line_end];
+                if line.strip_suffix('\r').unwrap_or(line).is_empty() {
+                    break line_start;
+                }
+                if line_end == rest.len() {
+                    break rest.len();
+                }
+                line_start = line_end + 1;
+            };
+
+            let (group, next_rest) = rest.split_at(group_end);
+            rest = next_rest;
+            Some(group.trim_end_matches(|c| c == '\r' || c == '\n'))
+        })
+    }
+
+    /// Like [`blank_line_separated_groups`], but skips groups that consist solely of whitespace,
+    /// like one made of trailing spaces at the end of a file.
+    pub fn records(s: &str) -> impl Iterator<Item = &str> {
+        blank_line_separated_groups(s).filter(|group| !group.trim().is_empty())
+    }
+
+    #[test]
+    fn blank_line_groups() {
+        let groups = |s| blank_line_separated_groups(s).collect::<Vec<_>>();
+        assert_eq!(groups("a\nb\n\nc\n"), ["a\nb", "c"]);
+        assert_eq!(groups("\na\r\n\r\n\r\nb\r\nc"), ["a", "b\r\nc"]);
+        assert!(groups("\n\n").is_empty());
+    }
+
+    #[test]
+    fn records_skip_whitespace_only_groups() {
+        let records = |s| records(s).collect::<Vec<_>>();
+        assert_eq!(records("a\nb\n\nc\n\n\n"), ["a\nb", "c"]);
+        assert_eq!(records("a\n\n  \t\n\nb"), ["a", "b"]);
+        assert!(records(" \n\n\n").is_empty());
+    }
+}
+
+pub mod grid {
+    use std::ops::Sub;
+
+    /// A position on an unbounded 2D grid, where `x` grows to the east and `y` grows to the
+    /// north.
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+    pub struct Point {
+        pub x: i64,
+        pub y: i64,
+    }
+
+    impl Point {
+        pub const ORIGIN: Self = Self { x: 0, y: 0 };
+
+        /// The number of orthogonal steps needed to get from `self` to `other`.
+        pub fn manhattan_distance(self, other: Self) -> u64 {
+            let abs_diff = |a: i64, b: i64| (i128::from(a) - i128::from(b)).unsigned_abs() as u64;
+            abs_diff(self.x, other.x) + abs_diff(self.y, other.y)
+        }
+    }
+
+    impl From<(i64, i64)> for Point {
+        fn from((x, y): (i64, i64)) -> Self {
+            Self { x, y }
+        }
+    }
+
+    impl Sub for Point {
+        type Output = Self;
+
+        /// Panics if either coordinate of the difference is unrepresentable with `i64`.
+        fn sub(self, other: Self) -> Self::Output {
+            let checked_sub = |a: i64, b: i64| {
+                a.checked_sub(b)
+                    .expect("difference of points is unrepresentable with `i64`")
+            };
+            Self {
+                x: checked_sub(self.x, other.x),
+                y: checked_sub(self.y, other.y),
+            }
+        }
+    }
+
+    #[test]
+    fn manhattan_distance() {
+        let point = Point { x: 17, y: -8 };
+        assert_eq!(Point::ORIGIN.manhattan_distance(point), 25);
+        assert_eq!(point.manhattan_distance(Point::ORIGIN), 25);
+        assert_eq!(point - Point { x: 7, y: 2 }, Point { x: 10, y: -10 });
+        assert_eq!(
+            Point { x: i64::MIN, y: 0 }.manhattan_distance(Point::ORIGIN),
+            1 << 63
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "difference of points is unrepresentable with `i64`")]
+    fn sub_overflow() {
+        let _ = Point { x: i64::MIN, y: 0 } - Point { x: 1, y: 0 };
+    }
 }
@@ -0,0 +1,40 @@
+use assert_cmd::Command;
+
+const D01_SAMPLE: &str = "\
+1721
+979
+366
+299
+675
+1456
+";
+
+#[test]
+fn d01p1_sample() {
+    Command::cargo_bin("aoc")
+        .unwrap()
+        .args(["--day", "1", "--part", "1"])
+        .write_stdin(D01_SAMPLE)
+        .assert()
+        .success()
+        .stdout("514579\n");
+}
+
+#[test]
+fn unknown_day() {
+    let output = Command::cargo_bin("aoc")
+        .unwrap()
+        .args(["--day", "99", "--part", "1"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("failed to solve part 1 of day 99"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("unknown day 99"), "{}", stderr);
+}
@@ -0,0 +1,13 @@
+use advent_of_code_2020::days::d04::{part_1, part_2};
+
+const INPUT: &str = include_str!("../src/days/d04.txt");
+
+#[test]
+fn d04_p1_answer() {
+    assert_eq!(part_1(INPUT).unwrap(), 239);
+}
+
+#[test]
+fn d04_p2_answer() {
+    assert_eq!(part_2(INPUT).unwrap(), 188);
+}
@@ -0,0 +1,27 @@
+use advent_of_code_2020::{
+    days::d06::{
+        sum_of_group_individuals_who_answered_yes_in_each_group,
+        sum_of_unique_question_answer_counts,
+    },
+    parsing::blank_line_separated_groups,
+};
+
+const INPUT: &str = include_str!("../src/days/d06.txt");
+
+#[test]
+fn d06_groups() {
+    assert_eq!(blank_line_separated_groups(INPUT).count(), 498);
+}
+
+#[test]
+fn d06_p1_answer() {
+    assert_eq!(sum_of_unique_question_answer_counts(INPUT), 7128);
+}
+
+#[test]
+fn d06_p2_answer() {
+    assert_eq!(
+        sum_of_group_individuals_who_answered_yes_in_each_group(INPUT),
+        3640
+    );
+}